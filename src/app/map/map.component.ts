@@ -3,7 +3,7 @@ import * as L from 'leaflet';
 import * as L2 from '../shared/utils/maps/HeatLayer';
 import { TrackService } from '../shared/services/track.service';
 import { Coordinate } from '../model/coordinate';
-import { FileList } from '../model/files';
+import { TrackList } from '../model/files';
 import { MatIconModule } from '@angular/material/icon';
 import { MatButtonModule } from '@angular/material/button';
 import { MatTooltip } from '@angular/material/tooltip';
@@ -86,8 +86,8 @@ export class MapComponent implements AfterViewInit {
 
   }
 
-  private displayTrack(filename: string): void {
-    this.trackService.getTrack(filename).subscribe((rawCoordinates: Coordinate[]) => {
+  private displayTrack(id: number): void {
+    this.trackService.getTrack(id).subscribe((rawCoordinates: Coordinate[]) => {
       const coordinates = rawCoordinates.map<L.LatLng>(coordinate => new L.LatLng(coordinate.a, coordinate.o));
       this.addTrackToMap(coordinates);
       this.downloadedTracks.update(currentValue => currentValue + 1);
@@ -114,13 +114,13 @@ export class MapComponent implements AfterViewInit {
 
     this.tracksToDownload.set(0);
     this.isLoadingTracks = true;
-    this.trackService.getTracksInsideSquare(northEast, southWest, this.trackFilters).subscribe((file: FileList) => {
+    this.trackService.getTracksInsideSquare(northEast, southWest, this.trackFilters).subscribe((trackList: TrackList) => {
 
-      const numberFilesFound = file.fileList.length;
+      const numberFilesFound = trackList.tracks.length;
       if (numberFilesFound > 0) {
         this.tracksToDownload.set(numberFilesFound);
         this.downloadedTracks.set(0);
-        file.fileList.forEach(filename => this.displayTrack(filename));
+        trackList.tracks.forEach(track => this.displayTrack(track.id));
       } else {
         this.isLoadingTracks = false;
       }
@@ -1,3 +1,8 @@
-export interface FileList {
-    fileList: string[]
+export interface TrackReference {
+    id: number
+    filename: string
+  }
+
+export interface TrackList {
+    tracks: TrackReference[]
   }
\ No newline at end of file
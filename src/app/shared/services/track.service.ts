@@ -2,7 +2,7 @@ import { Injectable } from '@angular/core';
 import L from 'leaflet';
 import { HttpClient } from '@angular/common/http';
 import { Coordinate } from '../../model/coordinate';
-import { FileList } from '../../model/files';
+import { TrackList } from '../../model/files';
 import { Observable } from 'rxjs';
 import { environment } from '../../environment/environment';
 import { TrackFilter } from '../../model/track-filter';
@@ -23,8 +23,8 @@ export class TrackService {
   constructor(private httpClient: HttpClient) { }
 
 
-  getTrack(filename: string): Observable<Coordinate[]> {
-    let url = `${this.backendUrl}/${this.coordinatesPath}/${filename}`;
+  getTrack(id: number): Observable<Coordinate[]> {
+    let url = `${this.backendUrl}/${this.coordinatesPath}/${id}`;
     return this.httpClient.get<Coordinate[]>(url);
   }
 
@@ -33,7 +33,7 @@ export class TrackService {
     return this.httpClient.get<ActivityTypes>(url);
   }
 
-  getTracksInsideSquare(northEastCoordinate: L.LatLng, southWestCoordinate: L.LatLng, filters?: TrackFilter): Observable<FileList> {
+  getTracksInsideSquare(northEastCoordinate: L.LatLng, southWestCoordinate: L.LatLng, filters?: TrackFilter): Observable<TrackList> {
     let params = `northWestLatitude=${northEastCoordinate.lat}&northWestLongitude=${southWestCoordinate.lng}&` +
       `southEastLatitude=${southWestCoordinate.lat}&southEastLongitude=${northEastCoordinate.lng}`;
 
@@ -42,7 +42,7 @@ export class TrackService {
     }
 
     const url = `${this.backendUrl}/${this.filteredTracksPath}?${params}`;
-    return this.httpClient.get<FileList>(url);
+    return this.httpClient.get<TrackList>(url);
   }
 
 }
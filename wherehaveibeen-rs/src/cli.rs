@@ -0,0 +1,550 @@
+use std::{
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::{
+    database::tracks::TracksDatabase,
+    files::{export::export_tracks, ingest, verify::verify_tracks},
+    model::{
+        filter::{SortOrder, TrackListOptions, TrackSortField},
+        query::{ExportFormat, TrackFilterQuery},
+        stats::StatisticsGroup,
+    },
+    utils::{
+        api_utils::Validate,
+        config::{get_cache_directory, get_dem_directory, get_tracks_directory},
+    },
+};
+
+#[derive(Parser)]
+#[command(version, about = "Heatmap and track server for GPS files")]
+pub struct Cli {
+    /// TOML configuration file. Defaults to `CONFIG_FILE` or `wherehaveibeen.toml` if it exists
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    /// Start the server when no command is given
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Process the new tracks and start the server
+    Serve(ServeArgs),
+    /// Process the files of a directory that have not been stored yet
+    Ingest(IngestArgs),
+    /// Compute the heatmap again from the cached coordinates of the stored tracks
+    RebuildHeatmap,
+    /// Print the totals of the stored tracks
+    Stats(StatsArgs),
+    /// Print the stored tracks matching the filters
+    List(ListArgs),
+    /// Write the tracks matching the filters to a single file
+    Export(ExportArgs),
+    /// Check that the database, the cached coordinates and the track files are consistent
+    Verify,
+    /// Guess the activity type of the tracks stored by older versions
+    Classify,
+    /// Take the elevations of the stored tracks from the DEM tiles and compute their climbs again
+    CorrectElevations,
+    /// Store the routes and waypoints of the GPX files stored by older versions
+    ImportLayers,
+}
+
+/// The address and port override the configuration
+#[derive(Args, Default)]
+pub struct ServeArgs {
+    /// Address the server listens on
+    #[arg(long)]
+    pub bind: Option<IpAddr>,
+    #[arg(long, short)]
+    pub port: Option<u16>,
+    /// Serve what is already stored without looking for new files
+    #[arg(long)]
+    pub no_ingest: bool,
+}
+
+#[derive(Args)]
+pub struct IngestArgs {
+    /// Defaults to the tracks directory. The server must be able to read the files from its own
+    /// tracks directory to return the original files.
+    pub directory: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum StatisticsGrouping {
+    Year,
+    Month,
+    Week,
+    ActivityType,
+}
+
+#[derive(Args)]
+pub struct StatsArgs {
+    #[arg(long, value_enum, default_value_t = StatisticsGrouping::Year)]
+    pub group_by: StatisticsGrouping,
+    /// Print all the groups as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args)]
+pub struct ListArgs {
+    #[arg(long, value_enum, default_value_t = TrackSortField::Date)]
+    pub sort_by: TrackSortField,
+    #[arg(long, value_enum, default_value_t = SortOrder::Descending)]
+    pub order: SortOrder,
+    #[arg(long, default_value_t = 50)]
+    pub limit: usize,
+    /// Print the tracks as JSON
+    #[arg(long)]
+    pub json: bool,
+    #[command(flatten)]
+    pub filter: FilterArgs,
+}
+
+/// Same filters as the `/tracks/filtered-tracks` endpoint
+#[derive(Args)]
+pub struct FilterArgs {
+    #[arg(long, allow_hyphen_values = true)]
+    pub north_west_latitude: Option<f64>,
+    #[arg(long, allow_hyphen_values = true)]
+    pub north_west_longitude: Option<f64>,
+    #[arg(long, allow_hyphen_values = true)]
+    pub south_east_latitude: Option<f64>,
+    #[arg(long, allow_hyphen_values = true)]
+    pub south_east_longitude: Option<f64>,
+    #[arg(long)]
+    pub activity_type: Option<String>,
+    /// `YYYY`, `YYYY-MM`, `YYYY-MM-DD` or a full RFC 3339 date
+    #[arg(long)]
+    pub start_date: Option<String>,
+    /// `YYYY`, `YYYY-MM`, `YYYY-MM-DD` or a full RFC 3339 date
+    #[arg(long)]
+    pub end_date: Option<String>,
+    /// Meters
+    #[arg(long)]
+    pub min_distance: Option<f64>,
+    /// Meters
+    #[arg(long)]
+    pub max_distance: Option<f64>,
+}
+
+impl FilterArgs {
+    pub fn to_query(&self) -> Result<TrackFilterQuery, String> {
+        let query = TrackFilterQuery {
+            north_west_latitude: self.north_west_latitude,
+            north_west_longitude: self.north_west_longitude,
+            south_east_latitude: self.south_east_latitude,
+            south_east_longitude: self.south_east_longitude,
+            activity_type: self.activity_type.clone(),
+            start_date: self.start_date.clone(),
+            end_date: self.end_date.clone(),
+            min_distance: self.min_distance,
+            max_distance: self.max_distance,
+        };
+        query.validate()?;
+
+        Ok(query)
+    }
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    #[arg(long, value_enum)]
+    pub format: ExportFormat,
+    /// File to write. Defaults to `tracks.<format>` in the current directory
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+    #[command(flatten)]
+    pub filter: FilterArgs,
+}
+
+pub fn ingest(args: IngestArgs) -> Result<(), String> {
+    let start = Instant::now();
+    let directory = args
+        .directory
+        .unwrap_or_else(|| PathBuf::from(get_tracks_directory()));
+
+    let result = ingest::ingest_directory(&directory).map_err(|e| e.to_string())?;
+    println!(
+        "Stored {} new tracks from {}. {} files could not be parsed",
+        result.new_tracks,
+        directory.display(),
+        result.invalid_files
+    );
+    if result.new_routes > 0 || result.new_waypoints > 0 {
+        println!(
+            "Stored {} routes and {} waypoints",
+            result.new_routes, result.new_waypoints
+        );
+    }
+    println!("Initialization took: {:?}", start.elapsed());
+
+    Ok(())
+}
+
+fn print_statistics_group(group: &StatisticsGroup) {
+    println!(
+        "{:<16} {:>7} {:>10.1} {:>8.1} {:>9.0}",
+        group.key,
+        group.count,
+        group.distance / 1000.0,
+        group.moving_time / 3600.0,
+        group.elevation_gain
+    );
+}
+
+pub fn stats(args: StatsArgs) -> Result<(), String> {
+    let tracks_db = TracksDatabase::new().map_err(|e| e.to_string())?;
+    let statistics = tracks_db.get_statistics().map_err(|e| e.to_string())?;
+
+    if args.json {
+        let json = serde_json::to_string_pretty(&statistics).map_err(|e| e.to_string())?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    let groups = match args.group_by {
+        StatisticsGrouping::Year => &statistics.by_year,
+        StatisticsGrouping::Month => &statistics.by_month,
+        StatisticsGrouping::Week => &statistics.by_week,
+        StatisticsGrouping::ActivityType => &statistics.by_activity_type,
+    };
+    println!(
+        "{:<16} {:>7} {:>10} {:>8} {:>9}",
+        "", "Tracks", "Km", "Hours", "Ascent m"
+    );
+    for group in groups {
+        print_statistics_group(group);
+    }
+    print_statistics_group(&statistics.total);
+
+    Ok(())
+}
+
+pub fn list(args: ListArgs) -> Result<(), String> {
+    let query = args.filter.to_query()?;
+    if args.limit == 0 {
+        return Err("limit must be greater than 0".to_string());
+    }
+    let options = TrackListOptions {
+        sort_by: args.sort_by,
+        order: args.order,
+        limit: args.limit,
+        cursor: None,
+    };
+
+    let tracks_db = TracksDatabase::new().map_err(|e| e.to_string())?;
+    let track_list = tracks_db
+        .get_track_list(&query.to_filter(), &options)
+        .map_err(|e| e.to_string())?;
+
+    if args.json {
+        let json = serde_json::to_string_pretty(&track_list.tracks).map_err(|e| e.to_string())?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    for track in &track_list.tracks {
+        println!(
+            "{:>6}  {:<10}  {:<16} {:>8.1} km  {}",
+            track.id,
+            track.date.get(..10).unwrap_or(&track.date),
+            track.activity_type,
+            track.metrics.distance / 1000.0,
+            track.display_name()
+        );
+    }
+    if track_list.next_cursor.is_some() {
+        println!("There are more tracks. Use --limit to show them.");
+    }
+
+    Ok(())
+}
+
+pub fn verify() -> Result<(), String> {
+    let tracks_directory = get_tracks_directory();
+    let cache_directory = get_cache_directory();
+    let problems = verify_tracks(Path::new(&tracks_directory), Path::new(&cache_directory))
+        .map_err(|e| e.to_string())?;
+
+    if problems.is_empty() {
+        println!("No problems found");
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("{}", problem);
+    }
+
+    Err(format!("{} problems found", problems.len()))
+}
+
+pub fn export(args: ExportArgs) -> Result<(), String> {
+    let query = args.filter.to_query()?;
+
+    let tracks_db = TracksDatabase::new().map_err(|e| e.to_string())?;
+    let tracks = tracks_db
+        .get_filtered_track_summaries(&query.to_filter())
+        .map_err(|e| e.to_string())?;
+    let number_of_tracks = tracks.len();
+
+    let tracks_directory = get_tracks_directory();
+    let cache_directory = get_cache_directory();
+    let content = export_tracks(
+        tracks,
+        args.format,
+        Path::new(&tracks_directory),
+        Path::new(&cache_directory),
+    )
+    .map_err(|e| e.to_string())?;
+
+    let output = args
+        .output
+        .unwrap_or_else(|| PathBuf::from(args.format.filename()));
+    std::fs::write(&output, content).map_err(|e| e.to_string())?;
+    println!(
+        "Exported {} tracks to {}",
+        number_of_tracks,
+        output.display()
+    );
+
+    Ok(())
+}
+
+pub fn rebuild_heatmap() -> Result<(), String> {
+    let result = ingest::rebuild_heatmap().map_err(|e| e.to_string())?;
+
+    println!(
+        "Heatmap rebuilt with {} points from {} tracks",
+        result.points, result.tracks
+    );
+    if !result.missing_tracks.is_empty() {
+        println!(
+            "Tracks without cached coordinates: {}",
+            result.missing_tracks.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+pub fn check_heatmap_decimals() -> Result<(), String> {
+    let result = ingest::rebuild_heatmap_if_decimals_changed().map_err(|e| e.to_string())?;
+
+    if let Some(result) = result {
+        println!(
+            "Heatmap rebuilt with {} points from {} tracks for the new number of decimals",
+            result.points, result.tracks
+        );
+    }
+
+    Ok(())
+}
+
+pub fn classify() -> Result<(), String> {
+    let result = ingest::classify_stored_tracks().map_err(|e| e.to_string())?;
+
+    println!(
+        "Activity type guessed for {} tracks",
+        result.classified_tracks
+    );
+    if !result.unclassified_files.is_empty() {
+        println!(
+            "Tracks that could not be classified: {}",
+            result.unclassified_files.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+pub fn correct_elevations() -> Result<(), String> {
+    if get_dem_directory().is_none() {
+        return Err("elevation.dem_directory is not configured".to_string());
+    }
+    let result = ingest::correct_stored_elevations().map_err(|e| e.to_string())?;
+
+    println!(
+        "Elevations corrected for {} tracks, {} tracks unchanged",
+        result.corrected_tracks, result.unchanged_tracks
+    );
+    if !result.failed_files.is_empty() {
+        println!(
+            "Tracks that could not be read: {}",
+            result.failed_files.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+pub fn import_layers() -> Result<(), String> {
+    let result = ingest::import_stored_layers().map_err(|e| e.to_string())?;
+
+    println!(
+        "Stored {} routes and {} waypoints",
+        result.routes, result.waypoints
+    );
+    if !result.failed_files.is_empty() {
+        println!(
+            "Files that could not be read: {}",
+            result.failed_files.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Option<Command> {
+        let args = ["wherehaveibeen-rs"].iter().chain(args);
+        Cli::try_parse_from(args).unwrap().command
+    }
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_without_command() {
+        assert!(parse(&[]).is_none());
+        let cli = Cli::try_parse_from(["wherehaveibeen-rs", "--config", "a.toml"]).unwrap();
+        assert_eq!(cli.config, Some(PathBuf::from("a.toml")));
+        assert!(Cli::try_parse_from(["wherehaveibeen-rs", "unknown"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_serve() {
+        let Some(Command::Serve(args)) = parse(&["serve"]) else {
+            panic!("Expected the serve command");
+        };
+        assert!(args.bind.is_none() && args.port.is_none() && !args.no_ingest);
+
+        let Some(Command::Serve(args)) =
+            parse(&["serve", "--bind", "127.0.0.1", "-p", "8080", "--no-ingest"])
+        else {
+            panic!("Expected the serve command");
+        };
+        assert_eq!(args.bind, Some("127.0.0.1".parse().unwrap()));
+        assert_eq!(args.port, Some(8080));
+        assert!(args.no_ingest);
+        assert!(Cli::try_parse_from(["wherehaveibeen-rs", "serve", "--port", "http"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_ingest() {
+        let Some(Command::Ingest(args)) = parse(&["ingest"]) else {
+            panic!("Expected the ingest command");
+        };
+        assert!(args.directory.is_none());
+
+        // The global options can be given after the command
+        let cli = Cli::try_parse_from(["wherehaveibeen-rs", "ingest", "new", "--config", "a.toml"])
+            .unwrap();
+        assert_eq!(cli.config, Some(PathBuf::from("a.toml")));
+        let Some(Command::Ingest(args)) = cli.command else {
+            panic!("Expected the ingest command");
+        };
+        assert_eq!(args.directory, Some(PathBuf::from("new")));
+    }
+
+    #[test]
+    fn test_parse_stats() {
+        let Some(Command::Stats(args)) = parse(&["stats"]) else {
+            panic!("Expected the stats command");
+        };
+        assert!(matches!(args.group_by, StatisticsGrouping::Year));
+        assert!(!args.json);
+
+        let Some(Command::Stats(args)) = parse(&["stats", "--group-by", "activity-type", "--json"])
+        else {
+            panic!("Expected the stats command");
+        };
+        assert!(matches!(args.group_by, StatisticsGrouping::ActivityType));
+        assert!(args.json);
+        assert!(Cli::try_parse_from(["wherehaveibeen-rs", "stats", "--group-by", "day"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let Some(Command::List(args)) = parse(&["list"]) else {
+            panic!("Expected the list command");
+        };
+        assert_eq!(args.sort_by, TrackSortField::Date);
+        assert_eq!(args.order, SortOrder::Descending);
+        assert_eq!(args.limit, 50);
+
+        let Some(Command::List(args)) = parse(&[
+            "list",
+            "--sort-by",
+            "distance",
+            "--order",
+            "asc",
+            "--limit",
+            "5",
+            "--north-west-longitude",
+            "-11.5",
+            "--activity-type",
+            "cycling",
+        ]) else {
+            panic!("Expected the list command");
+        };
+        assert_eq!(args.sort_by, TrackSortField::Distance);
+        assert_eq!(args.order, SortOrder::Ascending);
+        assert_eq!(args.limit, 5);
+        // Negative coordinates are not taken for options
+        assert_eq!(args.filter.north_west_longitude, Some(-11.5));
+        assert_eq!(args.filter.activity_type.as_deref(), Some("cycling"));
+    }
+
+    #[test]
+    fn test_parse_export() {
+        // The format is required
+        assert!(Cli::try_parse_from(["wherehaveibeen-rs", "export"]).is_err());
+
+        let Some(Command::Export(args)) = parse(&[
+            "export",
+            "--format",
+            "kml",
+            "-o",
+            "out.kml",
+            "--min-distance",
+            "1000",
+        ]) else {
+            panic!("Expected the export command");
+        };
+        assert_eq!(args.format, ExportFormat::Kml);
+        assert_eq!(args.output, Some(PathBuf::from("out.kml")));
+        assert_eq!(args.filter.min_distance, Some(1000.0));
+    }
+
+    #[test]
+    fn test_parse_commands_without_arguments() {
+        assert!(matches!(
+            parse(&["rebuild-heatmap"]),
+            Some(Command::RebuildHeatmap)
+        ));
+        assert!(matches!(parse(&["verify"]), Some(Command::Verify)));
+        assert!(matches!(parse(&["classify"]), Some(Command::Classify)));
+        assert!(matches!(
+            parse(&["correct-elevations"]),
+            Some(Command::CorrectElevations)
+        ));
+        assert!(matches!(
+            parse(&["import-layers"]),
+            Some(Command::ImportLayers)
+        ));
+        assert!(Cli::try_parse_from(["wherehaveibeen-rs", "verify", "extra"]).is_err());
+    }
+}
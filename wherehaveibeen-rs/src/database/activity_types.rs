@@ -0,0 +1,211 @@
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+use crate::{
+    model::activity_type::{ActivityTypeEntry, ActivityTypeMapping, ActivityTypeRegistry},
+    utils::config::get_database_path,
+};
+
+use super::query::activity_type::{
+    COUNT_ACTIVITY_TYPE_USES, DELETE_ACTIVITY_TYPE, DELETE_ACTIVITY_TYPE_MAPPING,
+    GET_ACTIVITY_TYPE, GET_ACTIVITY_TYPE_ANCESTORS, GET_ACTIVITY_TYPE_MAPPINGS,
+    GET_ACTIVITY_TYPE_REGISTRY, MOVE_ACTIVITY_SUBTYPES, RECLASSIFY_TRACKS, REGISTER_ACTIVITY_TYPE,
+    UPSERT_ACTIVITY_TYPE, UPSERT_ACTIVITY_TYPE_MAPPING,
+};
+
+pub struct ActivityTypesDatabase {
+    pub conn: Connection,
+}
+
+impl ActivityTypesDatabase {
+    pub fn new() -> Result<Self> {
+        let conn = Connection::open(get_database_path())?;
+        Ok(Self { conn })
+    }
+
+    pub fn get_registry(&self) -> Result<ActivityTypeRegistry> {
+        let mut stmt = self.conn.prepare(GET_ACTIVITY_TYPE_REGISTRY)?;
+        let activity_types = stmt
+            .query_map([], |row| {
+                Ok(ActivityTypeEntry {
+                    name: row.get(0)?,
+                    parent: row.get(1)?,
+                    track_count: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<ActivityTypeEntry>>>()?;
+
+        let mut stmt = self.conn.prepare(GET_ACTIVITY_TYPE_MAPPINGS)?;
+        let mappings = stmt
+            .query_map([], |row| {
+                Ok(ActivityTypeMapping {
+                    raw_type: row.get(0)?,
+                    activity_type: row.get(1)?,
+                })
+            })?
+            .collect::<Result<Vec<ActivityTypeMapping>>>()?;
+
+        Ok(ActivityTypeRegistry {
+            activity_types,
+            mappings,
+        })
+    }
+
+    /// Parent of the activity type. `None` if the type is not registered.
+    pub fn get_parent(&self, name: &str) -> Result<Option<Option<String>>> {
+        self.conn
+            .query_row(GET_ACTIVITY_TYPE, params![name], |row| row.get(1))
+            .optional()
+    }
+
+    /// The activity type itself and all its parents up to the root category
+    pub fn get_ancestors(&self, name: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(GET_ACTIVITY_TYPE_ANCESTORS)?;
+        let ancestors = stmt.query_map(params![name], |row| row.get(0))?;
+
+        ancestors.collect()
+    }
+
+    /// Tracks and mappings that use the activity type
+    pub fn count_uses(&self, name: &str) -> Result<i64> {
+        self.conn
+            .query_row(COUNT_ACTIVITY_TYPE_USES, params![name], |row| row.get(0))
+    }
+
+    /// Register the activity type under the given parent. The parent is registered if needed.
+    pub fn set_parent(&mut self, name: &str, parent: Option<&str>) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        if let Some(parent) = parent {
+            tx.execute(REGISTER_ACTIVITY_TYPE, params![parent])?;
+        }
+        tx.execute(UPSERT_ACTIVITY_TYPE, params![name, parent])?;
+        tx.commit()
+    }
+
+    /// The subtypes are moved to the parent of the removed type.
+    /// Returns false if the type is not registered.
+    pub fn delete_activity_type(&mut self, name: &str) -> Result<bool> {
+        let Some(parent) = self.get_parent(name)? else {
+            return Ok(false);
+        };
+
+        let tx = self.conn.transaction()?;
+        tx.execute(MOVE_ACTIVITY_SUBTYPES, params![name, parent])?;
+        tx.execute(DELETE_ACTIVITY_TYPE, params![name])?;
+        tx.commit()?;
+
+        Ok(true)
+    }
+
+    /// Store the tracks with the raw type as the given activity type from now on and change the
+    /// ones already stored. Returns the number of tracks that have changed.
+    pub fn set_mapping(&mut self, raw_type: &str, activity_type: &str) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            UPSERT_ACTIVITY_TYPE_MAPPING,
+            params![raw_type, activity_type],
+        )?;
+        tx.execute(REGISTER_ACTIVITY_TYPE, params![activity_type])?;
+        let reclassified_tracks =
+            tx.execute(RECLASSIFY_TRACKS, params![raw_type, activity_type])?;
+        tx.commit()?;
+
+        Ok(reclassified_tracks)
+    }
+
+    /// The tracks go back to their raw type. Returns the number of tracks that have changed or
+    /// `None` if there was no mapping.
+    pub fn delete_mapping(&mut self, raw_type: &str) -> Result<Option<usize>> {
+        let tx = self.conn.transaction()?;
+        if tx.execute(DELETE_ACTIVITY_TYPE_MAPPING, params![raw_type])? == 0 {
+            return Ok(None);
+        }
+        tx.execute(REGISTER_ACTIVITY_TYPE, params![raw_type])?;
+        let reclassified_tracks = tx.execute(RECLASSIFY_TRACKS, params![raw_type, raw_type])?;
+        tx.commit()?;
+
+        Ok(Some(reclassified_tracks))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        database::query::{heatmap::CREATE_HEATMAP_TABLE, track::CREATE_TRACKS_TABLE},
+        database::{migrations::run_migrations, tracks::TracksDatabase},
+        model::{filter::TrackFilter, track::TrackInformation},
+    };
+
+    fn insert_track(tracks_db: &TracksDatabase, filename: &str, activity_type: &str) {
+        let mut track_information = TrackInformation::create_empty_track();
+        track_information.activity_type = activity_type.to_string();
+        tracks_db
+            .insert_new_file(filename, track_information, false, filename)
+            .unwrap();
+    }
+
+    fn get_filtered_filenames(tracks_db: &TracksDatabase, activity_type: &str) -> Vec<String> {
+        let filter = TrackFilter {
+            activity_type: Some(activity_type.to_string()),
+            ..Default::default()
+        };
+        tracks_db
+            .get_filtered_tracks(&filter)
+            .unwrap()
+            .into_iter()
+            .map(|track| track.filename)
+            .collect()
+    }
+
+    #[test]
+    fn test_activity_type_registry() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute(CREATE_TRACKS_TABLE, ()).unwrap();
+        conn.execute(CREATE_HEATMAP_TABLE, ()).unwrap();
+        run_migrations(&mut conn).unwrap();
+
+        // The default conversions are the initial mappings
+        let tracks_db = TracksDatabase { conn };
+        insert_track(&tracks_db, "a.gpx", "Biking");
+        insert_track(&tracks_db, "b.gpx", "gravel");
+        assert_eq!(get_filtered_filenames(&tracks_db, "cycling"), vec!["a.gpx"]);
+
+        let mut activity_types_db = ActivityTypesDatabase {
+            conn: tracks_db.conn,
+        };
+        activity_types_db
+            .set_parent("gravel", Some("cycling"))
+            .unwrap();
+        assert!(activity_types_db
+            .get_ancestors("gravel")
+            .unwrap()
+            .contains(&"cycling".to_string()));
+
+        let tracks_db = TracksDatabase {
+            conn: activity_types_db.conn,
+        };
+        assert_eq!(
+            get_filtered_filenames(&tracks_db, "cycling"),
+            vec!["a.gpx", "b.gpx"]
+        );
+        assert_eq!(get_filtered_filenames(&tracks_db, "gravel"), vec!["b.gpx"]);
+
+        let mut activity_types_db = ActivityTypesDatabase {
+            conn: tracks_db.conn,
+        };
+        assert_eq!(activity_types_db.set_mapping("gravel", "mtb").unwrap(), 1);
+        assert_eq!(activity_types_db.delete_mapping("gravel").unwrap(), Some(1));
+        assert_eq!(activity_types_db.delete_mapping("gravel").unwrap(), None);
+
+        assert!(activity_types_db.delete_activity_type("cycling").unwrap());
+        let registry = activity_types_db.get_registry().unwrap();
+        let gravel = registry
+            .activity_types
+            .iter()
+            .find(|entry| entry.name == "gravel")
+            .unwrap();
+        assert_eq!(gravel.parent, None);
+        assert_eq!(gravel.track_count, 1);
+    }
+}
@@ -3,17 +3,101 @@ use std::collections::HashMap;
 use rusqlite::{named_params, params, params_from_iter, Connection, Result};
 
 use crate::{
-    model::{
-        coordinate::StringifiedCoordinate, heatmap::HeatmapCoordinate, track::TrackInformation,
-    },
-    utils::environment::get_database_path,
+    model::{coordinate::StringifiedCoordinate, filter::Bounds, heatmap::HeatmapCoordinate},
+    utils::config::get_database_path,
 };
 
+use super::metadata::set_heatmap_decimals;
 use super::query::heatmap::{
-    CREATE_HEATMAP_INDEX, CREATE_HEATMAP_TABLE, FILTER_HEATMAP_IN_LOCATION, GET_NR_HEATMAP_ROWS,
-    INSERT_DATA_INTO_HEATMAP, INSERT_OR_UPDATE_DATA_INTO_HEATMAP,
+    CREATE_HEATMAP_INDEX, CREATE_HEATMAP_TABLE, DELETE_EMPTY_HEATMAP_POINTS, DELETE_HEATMAP,
+    FILTER_HEATMAP_IN_LOCATION, GET_HEATMAP_TOTALS, GET_NR_HEATMAP_ROWS, INSERT_DATA_INTO_HEATMAP,
+    INSERT_OR_UPDATE_DATA_INTO_HEATMAP, SUBTRACT_DATA_FROM_HEATMAP,
 };
 
+/// Subtract the frequencies from the heatmap and remove the points that are no longer visited.
+/// It takes a connection so that it can be part of the transaction removing a track.
+pub fn subtract_from_heatmap(
+    conn: &Connection,
+    heatmap: &HashMap<StringifiedCoordinate, i32>,
+) -> Result<()> {
+    if heatmap.is_empty() {
+        return Ok(());
+    }
+
+    for (coordinate, frequency) in heatmap.iter() {
+        conn.execute(
+            SUBTRACT_DATA_FROM_HEATMAP,
+            params![
+                coordinate.latitude.to_string(),
+                coordinate.longitude.to_string(),
+                frequency.to_string()
+            ],
+        )?;
+    }
+    conn.execute(DELETE_EMPTY_HEATMAP_POINTS, [])?;
+
+    Ok(())
+}
+
+/// Add the frequencies to the heatmap. It takes a connection so that it can be part of the
+/// transaction storing a track.
+pub fn add_to_heatmap(
+    conn: &Connection,
+    heatmap: &HashMap<StringifiedCoordinate, i32>,
+) -> Result<()> {
+    let mut stmt = conn.prepare_cached(INSERT_OR_UPDATE_DATA_INTO_HEATMAP)?;
+    for (coordinate, frequency) in heatmap.iter() {
+        stmt.execute(params![
+            coordinate.latitude.to_string(),
+            coordinate.longitude.to_string(),
+            frequency.to_string()
+        ])?;
+    }
+
+    Ok(())
+}
+
+// Insert the points with several rows per query. It does not commit, so that the caller decides
+// the transaction they are part of.
+fn insert_rows_in_bulk(
+    conn: &Connection,
+    heatmap: &HashMap<StringifiedCoordinate, i32>,
+) -> Result<()> {
+    let chunk_size = 1000; // How many rows per query
+
+    let mut query = String::new();
+    let mut params = Vec::new();
+    for (counter, (coordinate, frequency)) in heatmap.iter().enumerate() {
+        // Start a new query if this is the first in the chunk
+        if counter % chunk_size == 0 {
+            if !query.is_empty() {
+                query.pop(); // Remove the trailing comma
+                conn.execute(&query, params_from_iter(params.iter()))?;
+                params.clear();
+            }
+
+            // Start a new query
+            query = String::from(INSERT_DATA_INTO_HEATMAP);
+        }
+
+        // Add placeholders to the query
+        query.push_str("(?, ?, ?),");
+
+        // Push values into the params vector
+        params.push(frequency.to_string());
+        params.push(coordinate.latitude.to_string());
+        params.push(coordinate.longitude.to_string());
+    }
+
+    // Execute any remaining query if there are leftover rows
+    if !query.is_empty() {
+        query.pop(); // Remove trailing comma
+        conn.execute(&query, params_from_iter(params.iter()))?;
+    }
+
+    Ok(())
+}
+
 pub struct HeatmapDatabase {
     pub conn: Connection,
 }
@@ -38,6 +122,12 @@ impl HeatmapDatabase {
         Ok(count == 0)
     }
 
+    /// Number of points and sum of their frequencies
+    pub fn get_totals(&self) -> Result<(i64, i64), rusqlite::Error> {
+        self.conn
+            .query_row(GET_HEATMAP_TOTALS, [], |row| Ok((row.get(0)?, row.get(1)?)))
+    }
+
     pub fn update_heatmap(
         &mut self,
         heatmap: &mut HashMap<StringifiedCoordinate, i32>,
@@ -52,76 +142,45 @@ impl HeatmapDatabase {
         Ok(())
     }
 
+    /// Replace the whole heatmap in a single transaction, so requests made while it is being
+    /// stored see the previous heatmap. `decimals` is the rounding of the new points.
+    pub fn rebuild_heatmap(
+        &mut self,
+        heatmap: &mut HashMap<StringifiedCoordinate, i32>,
+        decimals: usize,
+    ) -> Result<(), rusqlite::Error> {
+        let tx = self.conn.transaction()?;
+        tx.execute(DELETE_HEATMAP, [])?;
+        insert_rows_in_bulk(&tx, heatmap)?;
+        set_heatmap_decimals(&tx, decimals)?;
+        tx.commit()
+    }
+
     fn insert_data_in_bulk(
         &mut self,
         heatmap: &mut HashMap<StringifiedCoordinate, i32>,
     ) -> Result<(), rusqlite::Error> {
-        if heatmap.len() == 0 {
+        if heatmap.is_empty() {
             return Ok(());
         }
 
-        let transaction_size = 100; // How many queries before a commit
-        let chunk_size = 1000; // How many rows per query
-
-        let mut query = String::new();
-        let mut params = Vec::new();
-
-        let mut counter = 0;
-        let mut transaction_counter = 0;
-        let mut tx = self.conn.transaction()?;
         println!("Saving heatmap into the database...");
-        for (coordinate, frequency) in heatmap.into_iter() {
-            // Start a new query if this is the first in the chunk
-            if counter % chunk_size == 0 {
-                if !query.is_empty() {
-                    query.pop(); // Remove the trailing comma
-                    tx.execute(&query, params_from_iter(params.iter()))?;
-                    params.clear();
-                    transaction_counter += 1;
-                    // Commit all buffered transactions
-                    if transaction_counter % transaction_size == 0 {
-                        tx.commit()?;
-                        tx = self.conn.transaction()?;
-                    }
-                }
-
-                // Start a new query
-                query = String::from(INSERT_DATA_INTO_HEATMAP);
-            }
-
-            // Add placeholders to the query
-            query.push_str("(?, ?, ?),");
-
-            // Push values into the params vector
-            params.push(frequency.to_string());
-            params.push(coordinate.latitude.to_string());
-            params.push(coordinate.longitude.to_string());
-
-            counter += 1;
-        }
-
-        // Execute any remaining query if there are leftover rows
-        if !query.is_empty() {
-            query.pop(); // Remove trailing comma
-            tx.execute(&query, params_from_iter(params.iter()))?;
-        }
-        tx.commit()?;
-
-        Ok(())
+        let tx = self.conn.transaction()?;
+        insert_rows_in_bulk(&tx, heatmap)?;
+        tx.commit()
     }
 
     fn insert_data_or_update(
         &mut self,
         heatmap: &mut HashMap<StringifiedCoordinate, i32>,
     ) -> Result<(), rusqlite::Error> {
-        if heatmap.len() == 0 {
+        if heatmap.is_empty() {
             return Ok(());
         }
 
         let transaction_size = 1000;
         let mut tx = self.conn.transaction()?;
-        let mut counter = 0;
-        for (coordinate, frequency) in heatmap.into_iter() {
+        for (counter, (coordinate, frequency)) in heatmap.iter().enumerate() {
             if (counter % transaction_size) == (transaction_size - 1) {
                 tx.commit()?;
                 tx = self.conn.transaction()?;
@@ -134,7 +193,6 @@ impl HeatmapDatabase {
                     frequency.to_string()
                 ],
             )?;
-            counter += 1;
         }
         tx.commit()?;
 
@@ -156,42 +214,36 @@ impl HeatmapDatabase {
         Ok(())
     }
 
-    pub fn get_heatmap_inside_location(
-        &self,
-        track_information: TrackInformation,
-    ) -> Result<Vec<HeatmapCoordinate>> {
+    pub fn get_heatmap_inside_location(&self, bounds: &Bounds) -> Result<Vec<HeatmapCoordinate>> {
         let mut stmt = self.conn.prepare(FILTER_HEATMAP_IN_LOCATION)?;
 
-        let row_content = stmt
-            .query_map(
+        // An area crossing the antimeridian is queried as two ranges of longitude
+        let mut heatmap: Vec<HeatmapCoordinate> = Vec::new();
+        for (west_longitude, east_longitude) in bounds.longitude_ranges() {
+            let row_content = stmt.query_map(
                 named_params! {
-                    ":north_west_latitude": track_information.north_west_latitude,
-                    ":north_west_longitude": track_information.north_west_longitude,
-                    ":south_east_latitude": track_information.south_east_latitude,
-                    ":south_east_longitude": track_information.south_east_longitude,
+                    ":north_west_latitude": bounds.north_west_latitude,
+                    ":north_west_longitude": west_longitude,
+                    ":south_east_latitude": bounds.south_east_latitude,
+                    ":south_east_longitude": east_longitude,
                 },
                 |row| {
                     Ok((
-                        row.get::<_, f32>(0)?,
-                        row.get::<_, f32>(1)?,
-                        row.get::<_, i32>(2)?,
+                        row.get::<_, f64>(0)?,
+                        row.get::<_, f64>(1)?,
+                        row.get::<_, i64>(2)?,
                     ))
                 },
-            )
-            .unwrap();
+            )?;
 
-        let mut heatmap: Vec<HeatmapCoordinate> = Vec::new();
-        for row in row_content {
-            match row {
-                Ok((latitude, longitude, frequency)) => {
-                    heatmap.push(HeatmapCoordinate::new(
-                        latitude.to_string(),
-                        longitude.to_string(),
-                        frequency.to_string(),
-                    ));
-                }
-                Err(e) => {
-                    eprintln!("Error retrieving heatmap: {}", e);
+            for row in row_content {
+                match row {
+                    Ok((latitude, longitude, frequency)) => {
+                        heatmap.push(HeatmapCoordinate::new(latitude, longitude, frequency));
+                    }
+                    Err(e) => {
+                        eprintln!("Error retrieving heatmap: {}", e);
+                    }
                 }
             }
         }
@@ -199,3 +251,101 @@ impl HeatmapDatabase {
         Ok(heatmap)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::query::metadata::{CREATE_METADATA_TABLE, GET_HEATMAP_DECIMALS};
+
+    fn create_heatmap(points: &[(&str, &str, i32)]) -> HashMap<StringifiedCoordinate, i32> {
+        points
+            .iter()
+            .map(|(latitude, longitude, frequency)| {
+                (
+                    StringifiedCoordinate::new(latitude.to_string(), longitude.to_string()),
+                    *frequency,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_subtract_from_heatmap() {
+        let mut heatmap_db = HeatmapDatabase {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        heatmap_db.initialize_table().unwrap();
+        let mut heatmap =
+            create_heatmap(&[("47.50000", "11.10000", 3), ("47.60000", "11.20000", 1)]);
+        heatmap_db.update_heatmap(&mut heatmap).unwrap();
+
+        let track = create_heatmap(&[("47.50000", "11.10000", 1), ("47.60000", "11.20000", 1)]);
+        subtract_from_heatmap(&heatmap_db.conn, &track).unwrap();
+
+        let bounds = Bounds {
+            north_west_latitude: 48.0,
+            north_west_longitude: 11.0,
+            south_east_latitude: 47.0,
+            south_east_longitude: 12.0,
+        };
+        let points = heatmap_db.get_heatmap_inside_location(&bounds).unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].frequency, 2);
+    }
+    #[test]
+    fn test_heatmap_across_antimeridian() {
+        let mut heatmap_db = HeatmapDatabase {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        heatmap_db.initialize_table().unwrap();
+        let mut heatmap = create_heatmap(&[
+            ("-17.00000", "179.50000", 1),
+            ("-17.00000", "-179.50000", 1),
+            ("-17.00000", "0.00000", 1),
+        ]);
+        heatmap_db.update_heatmap(&mut heatmap).unwrap();
+
+        let bounds = Bounds {
+            north_west_latitude: -16.0,
+            north_west_longitude: 179.0,
+            south_east_latitude: -18.0,
+            south_east_longitude: -179.0,
+        };
+        let points = heatmap_db.get_heatmap_inside_location(&bounds).unwrap();
+        assert_eq!(points.len(), 2);
+    }
+
+    #[test]
+    fn test_rebuild_heatmap() {
+        let mut heatmap_db = HeatmapDatabase {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        heatmap_db.initialize_table().unwrap();
+        heatmap_db.conn.execute(CREATE_METADATA_TABLE, ()).unwrap();
+        let get_decimals = |heatmap_db: &HeatmapDatabase| -> i64 {
+            heatmap_db
+                .conn
+                .query_row(GET_HEATMAP_DECIMALS, [], |row| row.get(0))
+                .unwrap()
+        };
+        let mut heatmap =
+            create_heatmap(&[("47.50000", "11.10000", 3), ("47.60000", "11.20000", 1)]);
+        heatmap_db.update_heatmap(&mut heatmap).unwrap();
+
+        let mut heatmap = create_heatmap(&[("47.5000", "11.1000", 2)]);
+        heatmap_db.rebuild_heatmap(&mut heatmap, 4).unwrap();
+        assert_eq!(heatmap_db.get_totals().unwrap(), (1, 2));
+        assert_eq!(get_decimals(&heatmap_db), 4);
+
+        // A failed rebuild keeps the previous heatmap
+        heatmap_db
+            .conn
+            .execute_batch("CREATE TRIGGER fail BEFORE INSERT ON heatmap BEGIN SELECT RAISE(ABORT, 'fail'); END;")
+            .unwrap();
+        let mut heatmap = create_heatmap(&[("47.700", "11.300", 1)]);
+        assert!(heatmap_db.rebuild_heatmap(&mut heatmap, 3).is_err());
+        assert_eq!(heatmap_db.get_totals().unwrap(), (1, 2));
+        assert_eq!(get_decimals(&heatmap_db), 4);
+    }
+}
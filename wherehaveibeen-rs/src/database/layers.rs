@@ -0,0 +1,257 @@
+use rusqlite::{params, Connection, Result, ToSql};
+
+use crate::{
+    model::{
+        coordinate::Coordinate,
+        filter::Bounds,
+        layer::{FileLayers, Route, RouteSummary, Waypoint},
+    },
+    utils::config::get_database_path,
+};
+
+use super::query::layer::{
+    DELETE_FILE_ROUTES, DELETE_FILE_ROUTE_POINTS, DELETE_FILE_WAYPOINTS, GET_ROUTES_WITH_POINTS,
+    GET_WAYPOINTS, INSERT_ROUTE, INSERT_ROUTE_POINT, INSERT_WAYPOINT,
+};
+
+/// Remove the routes and waypoints of a file. It takes a connection so that it can be part of the
+/// transaction removing the track.
+pub fn delete_file_layers(conn: &Connection, filename: &str) -> Result<()> {
+    conn.execute(DELETE_FILE_ROUTE_POINTS, params![filename])?;
+    conn.execute(DELETE_FILE_ROUTES, params![filename])?;
+    conn.execute(DELETE_FILE_WAYPOINTS, params![filename])?;
+
+    Ok(())
+}
+
+/// Store the routes and waypoints of a file. It takes a connection so that it can be part of the
+/// transaction storing the track.
+pub fn insert_file_layers(conn: &Connection, filename: &str, layers: &FileLayers) -> Result<()> {
+    for route in &layers.routes {
+        conn.execute(
+            INSERT_ROUTE,
+            params![
+                filename,
+                route.summary.name,
+                route.summary.route_type,
+                route.summary.distance
+            ],
+        )?;
+        let id = conn.last_insert_rowid();
+        let mut stmt = conn.prepare_cached(INSERT_ROUTE_POINT)?;
+        for (position, coordinate) in route.coordinates.iter().enumerate() {
+            stmt.execute(params![
+                id,
+                position,
+                coordinate.latitude,
+                coordinate.longitude
+            ])?;
+        }
+    }
+    for waypoint in &layers.waypoints {
+        conn.execute(
+            INSERT_WAYPOINT,
+            params![
+                filename,
+                waypoint.name,
+                waypoint.description,
+                waypoint.symbol,
+                waypoint.waypoint_type,
+                waypoint.latitude,
+                waypoint.longitude,
+                waypoint.elevation,
+                waypoint.time
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn as_params<'a>(params: &'a [(&'static str, f64)]) -> Vec<(&'static str, &'a dyn ToSql)> {
+    params
+        .iter()
+        .map(|(name, value)| (*name, value as &dyn ToSql))
+        .collect()
+}
+
+// Parameters of the queries filtering by area. When the area does not cross the antimeridian both
+// longitude ranges are the same.
+fn get_area_params(bounds: &Bounds) -> [(&'static str, f64); 6] {
+    let ranges = bounds.longitude_ranges();
+    let (west, east) = ranges[0];
+    let (antimeridian_west, antimeridian_east) = ranges.get(1).copied().unwrap_or(ranges[0]);
+
+    [
+        (":north_west_latitude", bounds.north_west_latitude),
+        (":south_east_latitude", bounds.south_east_latitude),
+        (":west_longitude", west),
+        (":east_longitude", east),
+        (":antimeridian_west_longitude", antimeridian_west),
+        (":antimeridian_east_longitude", antimeridian_east),
+    ]
+}
+
+/// Routes and waypoints of the GPX files, shown as layers apart from the tracks
+pub struct LayersDatabase {
+    pub conn: Connection,
+}
+
+impl LayersDatabase {
+    pub fn new() -> Result<Self> {
+        let conn = Connection::open(get_database_path())?;
+        Ok(Self { conn })
+    }
+
+    /// Store the routes and waypoints of the file instead of the ones stored before
+    pub fn replace_file_layers(&mut self, filename: &str, layers: &FileLayers) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        delete_file_layers(&tx, filename)?;
+        insert_file_layers(&tx, filename, layers)?;
+        tx.commit()
+    }
+
+    /// Routes whose bounding box intersects the area
+    pub fn get_routes(&self, bounds: &Bounds) -> Result<Vec<Route>> {
+        let area_params = get_area_params(bounds);
+        let mut stmt = self.conn.prepare(GET_ROUTES_WITH_POINTS)?;
+        let mut rows = stmt.query(as_params(&area_params).as_slice())?;
+
+        let mut routes: Vec<Route> = Vec::new();
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            let coordinate = Coordinate::new(row.get(6)?, row.get(7)?);
+            match routes.last_mut() {
+                Some(route) if route.summary.id == id => route.coordinates.push(coordinate),
+                _ => routes.push(Route {
+                    summary: RouteSummary {
+                        id,
+                        filename: row.get(1)?,
+                        file_id: row.get(2)?,
+                        name: row.get(3)?,
+                        route_type: row.get(4)?,
+                        distance: row.get(5)?,
+                    },
+                    coordinates: vec![coordinate],
+                }),
+            }
+        }
+
+        Ok(routes)
+    }
+
+    /// Waypoints inside the area
+    pub fn get_waypoints(&self, bounds: &Bounds) -> Result<Vec<Waypoint>> {
+        let area_params = get_area_params(bounds);
+        let mut stmt = self.conn.prepare(GET_WAYPOINTS)?;
+        let waypoints = stmt.query_map(as_params(&area_params).as_slice(), |row| {
+            Ok(Waypoint {
+                id: row.get(0)?,
+                filename: row.get(1)?,
+                file_id: row.get(2)?,
+                name: row.get(3)?,
+                description: row.get(4)?,
+                symbol: row.get(5)?,
+                waypoint_type: row.get(6)?,
+                latitude: row.get(7)?,
+                longitude: row.get(8)?,
+                elevation: row.get(9)?,
+                time: row.get(10)?,
+            })
+        })?;
+
+        waypoints.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::query::{
+        layer::{CREATE_ROUTES_TABLE, CREATE_ROUTE_POINTS_TABLE, CREATE_WAYPOINTS_TABLE},
+        track::CREATE_TRACKS_TABLE,
+    };
+
+    fn create_waypoint(name: &str, latitude: f64, longitude: f64) -> Waypoint {
+        Waypoint {
+            id: 0,
+            filename: String::new(),
+            file_id: None,
+            name: Some(name.to_string()),
+            description: None,
+            symbol: None,
+            waypoint_type: None,
+            latitude,
+            longitude,
+            elevation: None,
+            time: None,
+        }
+    }
+
+    #[test]
+    fn test_get_layers_inside_area() {
+        let mut layers_db = LayersDatabase {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        for statement in [
+            CREATE_TRACKS_TABLE,
+            CREATE_ROUTES_TABLE,
+            CREATE_ROUTE_POINTS_TABLE,
+            CREATE_WAYPOINTS_TABLE,
+        ] {
+            layers_db.conn.execute(statement, ()).unwrap();
+        }
+        let layers = FileLayers {
+            routes: vec![Route {
+                summary: RouteSummary {
+                    id: 0,
+                    filename: String::new(),
+                    file_id: None,
+                    name: Some("Fiji".to_string()),
+                    route_type: None,
+                    distance: 0.0,
+                },
+                coordinates: vec![Coordinate::new(-17.0, 179.5), Coordinate::new(-17.1, 179.9)],
+            }],
+            waypoints: vec![
+                create_waypoint("Taveuni", -16.8, 179.9),
+                create_waypoint("Lau", -17.5, -179.5),
+                create_waypoint("Munich", 48.1, 11.6),
+            ],
+        };
+        layers_db.replace_file_layers("fiji.gpx", &layers).unwrap();
+        layers_db
+            .conn
+            .execute(
+                "INSERT INTO tracks (filename, north_west_latitude, north_west_longitude,
+                    south_east_latitude, south_east_longitude, is_empty_track, activity_type)
+                VALUES ('fiji.gpx', 0, 0, 0, 0, 1, '');",
+                (),
+            )
+            .unwrap();
+        let file_id = layers_db.conn.last_insert_rowid();
+
+        let bounds = Bounds {
+            north_west_latitude: -16.0,
+            north_west_longitude: 179.0,
+            south_east_latitude: -18.0,
+            south_east_longitude: -179.0,
+        };
+        let routes = layers_db.get_routes(&bounds).unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].summary.file_id, Some(file_id));
+        assert_eq!(layers_db.get_waypoints(&bounds).unwrap().len(), 2);
+
+        let bounds = Bounds {
+            north_west_latitude: 49.0,
+            north_west_longitude: 11.0,
+            south_east_latitude: 48.0,
+            south_east_longitude: 12.0,
+        };
+        assert!(layers_db.get_routes(&bounds).unwrap().is_empty());
+        let waypoints = layers_db.get_waypoints(&bounds).unwrap();
+        assert_eq!(waypoints.len(), 1);
+        assert_eq!(waypoints[0].name.as_deref(), Some("Munich"));
+    }
+}
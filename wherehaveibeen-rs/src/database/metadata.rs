@@ -0,0 +1,53 @@
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+use crate::utils::config::get_database_path;
+
+use super::query::metadata::{
+    GET_GENERATION, GET_HEATMAP_DECIMALS, INCREMENT_GENERATION, SET_HEATMAP_DECIMALS,
+};
+
+// It takes a connection so that it can be part of a bigger transaction
+pub fn increment_generation(conn: &Connection) -> Result<()> {
+    conn.execute(INCREMENT_GENERATION, [])?;
+
+    Ok(())
+}
+
+/// Number of decimals the points of the stored heatmap are rounded to. It takes a connection so
+/// that it can be part of the transaction storing the heatmap.
+pub fn set_heatmap_decimals(conn: &Connection, decimals: usize) -> Result<()> {
+    conn.execute(SET_HEATMAP_DECIMALS, params![decimals as i64])?;
+
+    Ok(())
+}
+
+pub struct MetadataDatabase {
+    pub conn: Connection,
+}
+
+impl MetadataDatabase {
+    pub fn new() -> Result<Self> {
+        let conn = Connection::open(get_database_path())?;
+        Ok(Self { conn })
+    }
+
+    /// Number that changes every time the stored tracks or the heatmap change. It is used to
+    /// invalidate the responses cached by the clients.
+    pub fn get_generation(&self) -> Result<i64> {
+        self.conn.query_row(GET_GENERATION, [], |row| row.get(0))
+    }
+
+    pub fn increment_generation(&self) -> Result<()> {
+        increment_generation(&self.conn)
+    }
+
+    /// Number of decimals the points of the stored heatmap are rounded to
+    pub fn get_heatmap_decimals(&self) -> Result<Option<usize>> {
+        let decimals: Option<i64> = self
+            .conn
+            .query_row(GET_HEATMAP_DECIMALS, [], |row| row.get(0))
+            .optional()?;
+
+        Ok(decimals.map(|decimals| decimals as usize))
+    }
+}
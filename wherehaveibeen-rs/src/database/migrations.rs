@@ -0,0 +1,364 @@
+use std::{collections::HashMap, path::Path};
+
+use rusqlite::{params, Connection, Result, Transaction};
+
+use crate::{
+    files::files::read_track_file,
+    utils::{
+        config::{get_config, get_tracks_directory},
+        file_access::resolve_path_inside,
+        file_utils::{get_content_hash, read_binary_file},
+    },
+};
+
+use super::heatmap::HeatmapDatabase;
+use super::metadata::set_heatmap_decimals;
+use super::query::activity_type::{
+    GET_ACTIVITY_TYPE_MAPPINGS, INSERT_ACTIVITY_TYPE_MAPPING_IF_MISSING,
+};
+use super::query::metadata::{CREATE_METADATA_TABLE, INITIALIZE_GENERATION};
+use super::query::migration::{
+    ADD_ACTIVITY_TYPE_REGISTRY, ADD_INFERRED_ACTIVITY_TYPE_COLUMNS, ADD_ROUTE_AND_WAYPOINT_TABLES,
+    ADD_TRACK_FILE_HASH_COLUMN, ADD_TRACK_METRICS_COLUMNS, ADD_TRACK_NAME_COLUMN,
+    ADD_TRACK_SPORT_COLUMNS, ADD_TRACK_UNTIMED_COLUMN, DELETE_ALL_HEATMAP_POINTS,
+    DELETE_ALL_TRACKS, GET_ALL_TRACK_FILES, GET_NON_EMPTY_TRACK_FILES, GET_SCHEMA_VERSION,
+    SET_RAW_ACTIVITY_TYPE, SET_SCHEMA_VERSION, SET_TRACK_FILE_HASH,
+};
+use super::tracks::TracksDatabase;
+
+struct Migration {
+    description: &'static str,
+    statements: &'static [&'static str],
+    // The stored data can not be upgraded in place and all the files need to be parsed again
+    reprocess_tracks: bool,
+    // Data that can not be written with plain statements
+    seed: Option<fn(&Transaction) -> Result<()>>,
+}
+
+// The configured conversions are the initial mappings. Afterwards they are managed with the API.
+fn seed_activity_type_mappings(tx: &Transaction) -> Result<()> {
+    for (raw_type, activity_type) in &get_config().activity_types {
+        tx.execute(
+            INSERT_ACTIVITY_TYPE_MAPPING_IF_MISSING,
+            params![raw_type, activity_type],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn get_track_files(tx: &Transaction, query: &str) -> Result<Vec<(i64, String)>> {
+    let mut stmt = tx.prepare(query)?;
+    let files = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    files.collect()
+}
+
+// The stored files are read again to compute their hash. The ones that can not be read keep an
+// empty hash, so they are not detected as duplicates.
+fn fill_file_hashes(tx: &Transaction, tracks_directory: &Path) -> Result<()> {
+    for (id, filename) in get_track_files(tx, GET_ALL_TRACK_FILES)? {
+        let content = resolve_path_inside(tracks_directory, &filename)
+            .and_then(|path| read_binary_file(&path));
+        match content {
+            Ok(content) => {
+                tx.execute(SET_TRACK_FILE_HASH, params![id, get_content_hash(&content)])?;
+            }
+            Err(e) => eprintln!("Can not compute the hash of {}: {}", filename, e),
+        }
+    }
+
+    Ok(())
+}
+
+fn seed_file_hashes(tx: &Transaction) -> Result<()> {
+    fill_file_hashes(tx, Path::new(&get_tracks_directory()))
+}
+
+// The stored activity type has already been mapped, so the one declared by the files is read
+// again. It stays empty for the files that do not declare one or can not be read.
+fn fill_raw_activity_types(tx: &Transaction, tracks_directory: &Path) -> Result<()> {
+    // Empty tracks are files that can not be parsed
+    for (id, filename) in get_track_files(tx, GET_NON_EMPTY_TRACK_FILES)? {
+        let track_file = resolve_path_inside(tracks_directory, &filename)
+            .and_then(|path| read_track_file(&path));
+        match track_file {
+            Ok(track_file) => {
+                if let Some(raw_type) = track_file.activity_type {
+                    tx.execute(SET_RAW_ACTIVITY_TYPE, params![id, raw_type])?;
+                }
+            }
+            Err(e) => eprintln!("Can not read the activity type of {}: {}", filename, e),
+        }
+    }
+
+    Ok(())
+}
+
+fn seed_activity_type_registry(tx: &Transaction) -> Result<()> {
+    seed_activity_type_mappings(tx)?;
+    fill_raw_activity_types(tx, Path::new(&get_tracks_directory()))
+}
+
+// The existing heatmap is assumed to be rounded to the configured number of decimals
+fn seed_heatmap_decimals(tx: &Transaction) -> Result<()> {
+    set_heatmap_decimals(tx, get_config().heatmap.decimals)
+}
+
+// The position of each migration in the list is its schema version. New migrations must only
+// be appended at the end.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "Store coordinates with double precision",
+        statements: &[],
+        reprocess_tracks: true,
+        seed: None,
+    },
+    Migration {
+        description: "Add distance, time and elevation metrics to the tracks",
+        statements: ADD_TRACK_METRICS_COLUMNS,
+        reprocess_tracks: true,
+        seed: None,
+    },
+    Migration {
+        description: "Add a generation counter to invalidate the cached responses",
+        statements: &[CREATE_METADATA_TABLE, INITIALIZE_GENERATION],
+        reprocess_tracks: false,
+        seed: None,
+    },
+    Migration {
+        description: "Store the hash of the files to detect duplicated uploads",
+        statements: ADD_TRACK_FILE_HASH_COLUMN,
+        reprocess_tracks: false,
+        seed: Some(seed_file_hashes),
+    },
+    Migration {
+        description: "Add a name that can be given to the tracks",
+        statements: ADD_TRACK_NAME_COLUMN,
+        reprocess_tracks: false,
+        seed: None,
+    },
+    Migration {
+        description: "Keep the raw activity type and add a registry of activity types",
+        statements: ADD_ACTIVITY_TYPE_REGISTRY,
+        reprocess_tracks: false,
+        seed: Some(seed_activity_type_registry),
+    },
+    Migration {
+        description: "Add the activity type guessed from the points",
+        statements: ADD_INFERRED_ACTIVITY_TYPE_COLUMNS,
+        reprocess_tracks: false,
+        seed: None,
+    },
+    Migration {
+        description: "Add the sport, sub-sport and sessions of the FIT files",
+        statements: ADD_TRACK_SPORT_COLUMNS,
+        reprocess_tracks: false,
+        seed: None,
+    },
+    Migration {
+        description: "Flag the tracks whose points do not have time",
+        statements: ADD_TRACK_UNTIMED_COLUMN,
+        reprocess_tracks: false,
+        seed: None,
+    },
+    Migration {
+        description: "Add the routes and waypoints of the GPX files",
+        statements: ADD_ROUTE_AND_WAYPOINT_TABLES,
+        reprocess_tracks: false,
+        seed: None,
+    },
+    Migration {
+        description: "Store the number of decimals of the heatmap points",
+        statements: &[],
+        reprocess_tracks: false,
+        seed: Some(seed_heatmap_decimals),
+    },
+];
+
+fn get_schema_version(conn: &Connection) -> Result<usize> {
+    let version: i64 = conn.query_row(GET_SCHEMA_VERSION, [], |row| row.get(0))?;
+    Ok(version as usize)
+}
+
+/// Create the tables if they do not exist and upgrade them to the latest schema version
+pub fn initialize_database() -> Result<()> {
+    let mut tracks_db = TracksDatabase::new()?;
+    let heatmap_db = HeatmapDatabase::new()?;
+
+    tracks_db.initialize_table()?;
+    heatmap_db.initialize_table()?;
+    run_migrations(&mut tracks_db.conn)?;
+
+    let changed_mappings =
+        get_changed_activity_type_mappings(&tracks_db.conn, &get_config().activity_types)?;
+    if !changed_mappings.is_empty() {
+        eprintln!(
+            "Warning: the configured activity type mappings of {} differ from the stored ones. \
+            The configuration only seeds a new database, use the /activity-types/mappings \
+            endpoints to change them.",
+            changed_mappings.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+// Configured mappings that are missing or different in the database. They are easy to miss
+// because the configuration is only read when the database is created.
+fn get_changed_activity_type_mappings(
+    conn: &Connection,
+    configured: &HashMap<String, String>,
+) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(GET_ACTIVITY_TYPE_MAPPINGS)?;
+    let stored = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<HashMap<String, String>>>()?;
+
+    let mut changed: Vec<String> = configured
+        .iter()
+        .filter(|(raw_type, activity_type)| stored.get(*raw_type) != Some(activity_type))
+        .map(|(raw_type, _)| raw_type.clone())
+        .collect();
+    changed.sort();
+
+    Ok(changed)
+}
+
+/// Upgrade the database to the latest schema version. Must be called once the tables exist.
+pub fn run_migrations(conn: &mut Connection) -> Result<()> {
+    let current_version = get_schema_version(conn)?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current_version) {
+        let version = index + 1;
+        println!(
+            "Migrating database to version {}: {}",
+            version, migration.description
+        );
+
+        let tx = conn.transaction()?;
+        for statement in migration.statements {
+            tx.execute(statement, [])?;
+        }
+        if let Some(seed) = migration.seed {
+            seed(&tx)?;
+        }
+        if migration.reprocess_tracks {
+            tx.execute(DELETE_ALL_TRACKS, [])?;
+            tx.execute(DELETE_ALL_HEATMAP_POINTS, [])?;
+        }
+        tx.execute(&format!("{}{};", SET_SCHEMA_VERSION, version), [])?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::query::{
+        heatmap::CREATE_HEATMAP_TABLE, metadata::GET_GENERATION, track::CREATE_TRACKS_TABLE,
+    };
+
+    #[test]
+    fn test_run_migrations() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute(CREATE_TRACKS_TABLE, ()).unwrap();
+        conn.execute(CREATE_HEATMAP_TABLE, ()).unwrap();
+
+        run_migrations(&mut conn).unwrap();
+        assert_eq!(get_schema_version(&conn).unwrap(), MIGRATIONS.len());
+        let generation: i64 = conn
+            .query_row(GET_GENERATION, [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(generation, 0);
+
+        // Running them again does nothing
+        run_migrations(&mut conn).unwrap();
+        assert_eq!(get_schema_version(&conn).unwrap(), MIGRATIONS.len());
+    }
+
+    // Database with a track of the fixture file and one whose file is missing
+    fn create_database_with_tracks() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute(CREATE_TRACKS_TABLE, ()).unwrap();
+        conn.execute(CREATE_HEATMAP_TABLE, ()).unwrap();
+        run_migrations(&mut conn).unwrap();
+        for filename in ["garmin-fenix-5-bike.fit", "missing.gpx"] {
+            conn.execute(
+                "INSERT INTO tracks (filename, north_west_latitude, north_west_longitude,
+                    south_east_latitude, south_east_longitude, is_empty_track, activity_type)
+                VALUES (?1, 0, 0, 0, 0, 0, 'biking');",
+                [filename],
+            )
+            .unwrap();
+        }
+
+        conn
+    }
+
+    fn get_column<T: rusqlite::types::FromSql>(conn: &Connection, column: &str) -> Vec<T> {
+        let mut stmt = conn
+            .prepare(&format!("SELECT {} FROM tracks ORDER BY id;", column))
+            .unwrap();
+        let values = stmt.query_map([], |row| row.get(0)).unwrap();
+
+        values.collect::<Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn test_fill_file_hashes() {
+        let mut conn = create_database_with_tracks();
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/fixtures");
+        let tx = conn.transaction().unwrap();
+        fill_file_hashes(&tx, &fixtures).unwrap();
+        tx.commit().unwrap();
+
+        let content = read_binary_file(&fixtures.join("garmin-fenix-5-bike.fit")).unwrap();
+        assert_eq!(
+            get_column::<Option<String>>(&conn, "file_hash"),
+            vec![Some(get_content_hash(&content)), None]
+        );
+    }
+
+    #[test]
+    fn test_fill_raw_activity_types() {
+        let mut conn = create_database_with_tracks();
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/fixtures");
+        let tx = conn.transaction().unwrap();
+        fill_raw_activity_types(&tx, &fixtures).unwrap();
+        tx.commit().unwrap();
+
+        // The mapped activity type is not taken as the raw one
+        assert_eq!(
+            get_column::<String>(&conn, "raw_activity_type"),
+            vec!["cycling", ""]
+        );
+        assert_eq!(
+            get_column::<String>(&conn, "activity_type"),
+            vec!["biking", "biking"]
+        );
+    }
+
+    #[test]
+    fn test_changed_activity_type_mappings() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute(CREATE_TRACKS_TABLE, ()).unwrap();
+        conn.execute(CREATE_HEATMAP_TABLE, ()).unwrap();
+        run_migrations(&mut conn).unwrap();
+
+        let mut configured = get_config().activity_types.clone();
+        assert!(get_changed_activity_type_mappings(&conn, &configured)
+            .unwrap()
+            .is_empty());
+
+        configured.insert("hiking".to_string(), "hiking".to_string());
+        configured.insert("gravel".to_string(), "cycling".to_string());
+        assert_eq!(
+            get_changed_activity_type_mappings(&conn, &configured).unwrap(),
+            vec!["gravel", "hiking"]
+        );
+    }
+}
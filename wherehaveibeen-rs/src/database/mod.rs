@@ -1,3 +1,7 @@
 pub mod tracks;
 pub mod heatmap;
-mod query;
\ No newline at end of file
+pub mod metadata;
+pub mod migrations;
+mod query;
+pub mod activity_types;
+pub mod layers;
\ No newline at end of file
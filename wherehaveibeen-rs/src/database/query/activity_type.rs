@@ -0,0 +1,86 @@
+pub const CREATE_ACTIVITY_TYPES_TABLE: &str = "
+    CREATE TABLE IF NOT EXISTS activity_types (
+        name TEXT PRIMARY KEY,
+        parent TEXT
+    );
+";
+
+pub const CREATE_ACTIVITY_TYPE_MAPPINGS_TABLE: &str = "
+    CREATE TABLE IF NOT EXISTS activity_type_mappings (
+        raw_type TEXT PRIMARY KEY,
+        activity_type TEXT NOT NULL
+    );
+";
+
+pub const INSERT_ACTIVITY_TYPE_MAPPING_IF_MISSING: &str = "
+    INSERT OR IGNORE INTO activity_type_mappings (raw_type, activity_type) VALUES (?1, ?2);
+";
+
+pub const UPSERT_ACTIVITY_TYPE_MAPPING: &str = "
+INSERT INTO activity_type_mappings (raw_type, activity_type)
+VALUES (?1, ?2)
+ON CONFLICT(raw_type)
+DO UPDATE SET activity_type = excluded.activity_type;";
+
+pub const DELETE_ACTIVITY_TYPE_MAPPING: &str = "
+    DELETE FROM activity_type_mappings WHERE raw_type = ?1;
+";
+
+pub const GET_ACTIVITY_TYPE_MAPPINGS: &str = "
+    SELECT raw_type, activity_type FROM activity_type_mappings ORDER BY raw_type;
+";
+
+// Tracks whose activity type has been set by hand are kept as they are
+pub const RECLASSIFY_TRACKS: &str = "
+UPDATE tracks
+SET activity_type = ?2
+WHERE
+    raw_activity_type = ?1
+    AND activity_type != ?2
+    AND activity_type_edited IS FALSE
+    AND is_empty_track IS FALSE;";
+
+pub const REGISTER_ACTIVITY_TYPE: &str = "
+    INSERT OR IGNORE INTO activity_types (name) VALUES (?1);
+";
+
+pub const UPSERT_ACTIVITY_TYPE: &str = "
+INSERT INTO activity_types (name, parent)
+VALUES (?1, ?2)
+ON CONFLICT(name)
+DO UPDATE SET parent = excluded.parent;";
+
+pub const DELETE_ACTIVITY_TYPE: &str = "
+    DELETE FROM activity_types WHERE name = ?1;
+";
+
+pub const MOVE_ACTIVITY_SUBTYPES: &str = "
+    UPDATE activity_types SET parent = ?2 WHERE parent = ?1;
+";
+
+pub const GET_ACTIVITY_TYPE: &str = "
+    SELECT name, parent FROM activity_types WHERE name = ?1;
+";
+
+pub const GET_ACTIVITY_TYPE_REGISTRY: &str = "
+SELECT
+    a.name,
+    a.parent,
+    (SELECT COUNT(*) FROM tracks t WHERE t.activity_type = a.name AND t.is_empty_track IS FALSE)
+FROM activity_types a
+ORDER BY a.name;";
+
+pub const COUNT_ACTIVITY_TYPE_USES: &str = "
+SELECT
+    (SELECT COUNT(*) FROM tracks t WHERE t.activity_type = ?1 AND t.is_empty_track IS FALSE)
+    + (SELECT COUNT(*) FROM activity_type_mappings m WHERE m.activity_type = ?1);";
+
+// The type itself and all its parents up to the root
+pub const GET_ACTIVITY_TYPE_ANCESTORS: &str = "
+WITH RECURSIVE ancestors(name) AS (
+    SELECT ?1
+    UNION
+    SELECT a.parent FROM activity_types a JOIN ancestors ON a.name = ancestors.name
+    WHERE a.parent IS NOT NULL
+)
+SELECT name FROM ancestors;";
@@ -8,6 +8,10 @@ CREATE TABLE IF NOT EXISTS heatmap (
 
 pub const GET_NR_HEATMAP_ROWS: &str = "SELECT COUNT(*) FROM heatmap;";
 
+pub const GET_HEATMAP_TOTALS: &str = "SELECT COUNT(*), COALESCE(SUM(frequency), 0) FROM heatmap;";
+
+pub const DELETE_HEATMAP: &str = "DELETE FROM heatmap;";
+
 pub const INSERT_DATA_INTO_HEATMAP: &str = "
 INSERT INTO
     heatmap (
@@ -19,6 +23,13 @@ VALUES (?1, ?2, ?3)
 ON CONFLICT(latitude, longitude) 
 DO UPDATE SET frequency = frequency + excluded.frequency;";
 
+pub const SUBTRACT_DATA_FROM_HEATMAP: &str = "
+UPDATE heatmap
+SET frequency = frequency - ?3
+WHERE latitude = ?1 AND longitude = ?2;";
+
+pub const DELETE_EMPTY_HEATMAP_POINTS: &str = "DELETE FROM heatmap WHERE frequency <= 0;";
+
 pub const CREATE_HEATMAP_INDEX: &str =
     "CREATE INDEX idx_lat_long ON heatmap (latitude, longitude);";
 
@@ -0,0 +1,137 @@
+pub const CREATE_ROUTES_TABLE: &str = "
+    CREATE TABLE IF NOT EXISTS routes (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        filename TEXT NOT NULL,
+        name TEXT,
+        type TEXT,
+        distance REAL NOT NULL
+    );
+";
+
+pub const CREATE_ROUTE_POINTS_TABLE: &str = "
+    CREATE TABLE IF NOT EXISTS route_points (
+        route_id INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        latitude REAL NOT NULL,
+        longitude REAL NOT NULL,
+        PRIMARY KEY (route_id, position)
+    );
+";
+
+pub const CREATE_WAYPOINTS_TABLE: &str = "
+    CREATE TABLE IF NOT EXISTS waypoints (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        filename TEXT NOT NULL,
+        name TEXT,
+        description TEXT,
+        symbol TEXT,
+        type TEXT,
+        latitude REAL NOT NULL,
+        longitude REAL NOT NULL,
+        elevation REAL,
+        time TEXT
+    );
+";
+
+pub const CREATE_ROUTE_FILENAME_INDEX: &str = "
+    CREATE INDEX IF NOT EXISTS idx_route_filename ON routes (filename);
+";
+
+pub const CREATE_WAYPOINT_FILENAME_INDEX: &str = "
+    CREATE INDEX IF NOT EXISTS idx_waypoint_filename ON waypoints (filename);
+";
+
+pub const INSERT_ROUTE: &str = "
+    INSERT INTO routes (filename, name, type, distance) VALUES (?1, ?2, ?3, ?4);
+";
+
+pub const INSERT_ROUTE_POINT: &str = "
+    INSERT INTO route_points (route_id, position, latitude, longitude) VALUES (?1, ?2, ?3, ?4);
+";
+
+pub const INSERT_WAYPOINT: &str = "
+INSERT INTO
+    waypoints (
+        filename,
+        name,
+        description,
+        symbol,
+        type,
+        latitude,
+        longitude,
+        elevation,
+        time
+    )
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9);
+";
+
+pub const DELETE_FILE_ROUTE_POINTS: &str = "
+    DELETE FROM route_points WHERE route_id IN (SELECT id FROM routes WHERE filename = ?1);
+";
+
+pub const DELETE_FILE_ROUTES: &str = "
+    DELETE FROM routes WHERE filename = ?1;
+";
+
+pub const DELETE_FILE_WAYPOINTS: &str = "
+    DELETE FROM waypoints WHERE filename = ?1;
+";
+
+// One row per point. The points of each route are consecutive. The bounding box of the route
+// must intersect the area, which has a second longitude range when it crosses the antimeridian.
+pub const GET_ROUTES_WITH_POINTS: &str = "
+WITH route_bounds AS (
+    SELECT
+        route_id,
+        MAX(latitude) AS north_latitude,
+        MIN(latitude) AS south_latitude,
+        MIN(longitude) AS west_longitude,
+        MAX(longitude) AS east_longitude
+    FROM route_points
+    GROUP BY route_id
+)
+SELECT
+    r.id,
+    r.filename,
+    (SELECT t.id FROM tracks t WHERE t.filename = r.filename LIMIT 1),
+    r.name,
+    r.type,
+    r.distance,
+    p.latitude,
+    p.longitude
+FROM routes r
+JOIN route_bounds b ON b.route_id = r.id
+JOIN route_points p ON p.route_id = r.id
+WHERE
+    b.north_latitude >= :south_east_latitude
+    AND b.south_latitude <= :north_west_latitude
+    AND (
+        (b.east_longitude >= :west_longitude AND b.west_longitude <= :east_longitude)
+        OR (
+            b.east_longitude >= :antimeridian_west_longitude
+            AND b.west_longitude <= :antimeridian_east_longitude
+        )
+    )
+ORDER BY r.id, p.position;";
+
+pub const GET_WAYPOINTS: &str = "
+SELECT
+    w.id,
+    w.filename,
+    (SELECT t.id FROM tracks t WHERE t.filename = w.filename LIMIT 1),
+    w.name,
+    w.description,
+    w.symbol,
+    w.type,
+    w.latitude,
+    w.longitude,
+    w.elevation,
+    w.time
+FROM waypoints w
+WHERE
+    w.latitude BETWEEN :south_east_latitude AND :north_west_latitude
+    AND (
+        w.longitude BETWEEN :west_longitude AND :east_longitude
+        OR w.longitude BETWEEN :antimeridian_west_longitude AND :antimeridian_east_longitude
+    )
+ORDER BY w.id;";
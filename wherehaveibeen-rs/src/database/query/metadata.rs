@@ -0,0 +1,27 @@
+pub const CREATE_METADATA_TABLE: &str = "
+    CREATE TABLE IF NOT EXISTS metadata (
+        key TEXT PRIMARY KEY,
+        value INTEGER NOT NULL
+    );
+";
+
+pub const INITIALIZE_GENERATION: &str = "
+    INSERT OR IGNORE INTO metadata (key, value) VALUES ('generation', 0);
+";
+
+pub const GET_GENERATION: &str = "
+    SELECT value FROM metadata WHERE key = 'generation';
+";
+
+pub const INCREMENT_GENERATION: &str = "
+    UPDATE metadata SET value = value + 1 WHERE key = 'generation';
+";
+
+pub const GET_HEATMAP_DECIMALS: &str = "
+    SELECT value FROM metadata WHERE key = 'heatmap_decimals';
+";
+
+pub const SET_HEATMAP_DECIMALS: &str = "
+    INSERT INTO metadata (key, value) VALUES ('heatmap_decimals', ?1)
+    ON CONFLICT(key) DO UPDATE SET value = excluded.value;
+";
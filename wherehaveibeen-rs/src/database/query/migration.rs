@@ -0,0 +1,83 @@
+use super::activity_type::{CREATE_ACTIVITY_TYPES_TABLE, CREATE_ACTIVITY_TYPE_MAPPINGS_TABLE};
+use super::layer::{
+    CREATE_ROUTES_TABLE, CREATE_ROUTE_FILENAME_INDEX, CREATE_ROUTE_POINTS_TABLE,
+    CREATE_WAYPOINTS_TABLE, CREATE_WAYPOINT_FILENAME_INDEX,
+};
+use super::track::CREATE_TRACK_SESSIONS_TABLE;
+
+pub const GET_SCHEMA_VERSION: &str = "PRAGMA user_version;";
+
+// PRAGMA statements do not accept bound parameters
+pub const SET_SCHEMA_VERSION: &str = "PRAGMA user_version = ";
+
+pub const DELETE_ALL_TRACKS: &str = "DELETE FROM tracks;";
+
+pub const DELETE_ALL_HEATMAP_POINTS: &str = "DELETE FROM heatmap;";
+
+pub const ADD_TRACK_METRICS_COLUMNS: &[&str] = &[
+    "ALTER TABLE tracks ADD COLUMN distance REAL NOT NULL DEFAULT 0;",
+    "ALTER TABLE tracks ADD COLUMN elapsed_time REAL NOT NULL DEFAULT 0;",
+    "ALTER TABLE tracks ADD COLUMN moving_time REAL NOT NULL DEFAULT 0;",
+    "ALTER TABLE tracks ADD COLUMN average_speed REAL NOT NULL DEFAULT 0;",
+    "ALTER TABLE tracks ADD COLUMN max_speed REAL NOT NULL DEFAULT 0;",
+    "ALTER TABLE tracks ADD COLUMN elevation_gain REAL;",
+    "ALTER TABLE tracks ADD COLUMN elevation_loss REAL;",
+    "ALTER TABLE tracks ADD COLUMN min_altitude REAL;",
+    "ALTER TABLE tracks ADD COLUMN max_altitude REAL;",
+];
+
+pub const ADD_TRACK_FILE_HASH_COLUMN: &[&str] = &[
+    "ALTER TABLE tracks ADD COLUMN file_hash TEXT;",
+    "CREATE INDEX IF NOT EXISTS idx_file_hash ON tracks (file_hash);",
+];
+
+pub const GET_ALL_TRACK_FILES: &str = "SELECT id, filename FROM tracks;";
+
+pub const GET_NON_EMPTY_TRACK_FILES: &str =
+    "SELECT id, filename FROM tracks WHERE is_empty_track IS FALSE;";
+
+pub const SET_TRACK_FILE_HASH: &str = "UPDATE tracks SET file_hash = ?2 WHERE id = ?1;";
+
+pub const SET_RAW_ACTIVITY_TYPE: &str = "UPDATE tracks SET raw_activity_type = ?2 WHERE id = ?1;";
+
+pub const ADD_TRACK_NAME_COLUMN: &[&str] = &["ALTER TABLE tracks ADD COLUMN name TEXT;"];
+
+// The activity type of the existing tracks has already been mapped. The raw one is read again from
+// the files.
+pub const ADD_ACTIVITY_TYPE_REGISTRY: &[&str] = &[
+    CREATE_ACTIVITY_TYPES_TABLE,
+    CREATE_ACTIVITY_TYPE_MAPPINGS_TABLE,
+    "ALTER TABLE tracks ADD COLUMN raw_activity_type TEXT NOT NULL DEFAULT '';",
+    "ALTER TABLE tracks ADD COLUMN activity_type_edited INTEGER NOT NULL DEFAULT 0;",
+    "INSERT OR IGNORE INTO activity_types (name)
+        SELECT DISTINCT activity_type FROM tracks WHERE activity_type != '';",
+];
+
+// The guessed type is filled in by the `classify` command as the files need to be parsed again
+pub const ADD_INFERRED_ACTIVITY_TYPE_COLUMNS: &[&str] = &[
+    "ALTER TABLE tracks ADD COLUMN inferred_activity_type TEXT;",
+    "ALTER TABLE tracks ADD COLUMN inferred_activity_type_confidence REAL;",
+];
+
+// The sport of the FIT files was stored as their activity type. The sub-sport and the sessions
+// are only known for the files stored from now on.
+pub const ADD_TRACK_SPORT_COLUMNS: &[&str] = &[
+    CREATE_TRACK_SESSIONS_TABLE,
+    "ALTER TABLE tracks ADD COLUMN sport TEXT;",
+    "ALTER TABLE tracks ADD COLUMN sub_sport TEXT;",
+    "UPDATE tracks SET sport = raw_activity_type
+        WHERE filename LIKE '%.fit' AND raw_activity_type != '';",
+];
+
+// Points without time used to be skipped, so all the stored tracks have time
+pub const ADD_TRACK_UNTIMED_COLUMN: &[&str] =
+    &["ALTER TABLE tracks ADD COLUMN is_untimed INTEGER NOT NULL DEFAULT 0;"];
+
+// The layers of the files that are already stored are imported with the `import-layers` command
+pub const ADD_ROUTE_AND_WAYPOINT_TABLES: &[&str] = &[
+    CREATE_ROUTES_TABLE,
+    CREATE_ROUTE_POINTS_TABLE,
+    CREATE_WAYPOINTS_TABLE,
+    CREATE_ROUTE_FILENAME_INDEX,
+    CREATE_WAYPOINT_FILENAME_INDEX,
+];
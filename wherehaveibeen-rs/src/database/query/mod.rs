@@ -1,2 +1,6 @@
 pub mod track;
-pub mod heatmap;
\ No newline at end of file
+pub mod heatmap;
+pub mod metadata;
+pub mod migration;
+pub mod activity_type;
+pub mod layer;
\ No newline at end of file
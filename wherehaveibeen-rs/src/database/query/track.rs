@@ -16,6 +16,10 @@ pub const GET_ALL_TRACK_FILENAMES: &str = "
     SELECT filename FROM tracks;
 ";
 
+pub const GET_TRACK_FILENAME: &str = "
+    SELECT filename FROM tracks WHERE id = ?1 AND is_empty_track IS FALSE;
+";
+
 pub const CREATE_TRACK_FILENAME_INDEX: &str = "
     CREATE INDEX idx_filename ON tracks (filename);
 ";
@@ -40,11 +44,161 @@ INSERT INTO
             south_east_longitude,
             is_empty_track,
             date,
-            activity_type
+            activity_type,
+            distance,
+            elapsed_time,
+            moving_time,
+            average_speed,
+            max_speed,
+            elevation_gain,
+            elevation_loss,
+            min_altitude,
+            max_altitude,
+            file_hash,
+            raw_activity_type,
+            inferred_activity_type,
+            inferred_activity_type_confidence,
+            sport,
+            sub_sport,
+            is_untimed
         ) 
-    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+    VALUES (
+        ?1, ?2, ?3, ?4, ?5, ?6, ?7,
+        COALESCE((SELECT m.activity_type FROM activity_type_mappings m WHERE m.raw_type = ?8), ?8),
+        ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24
+    )
+";
+
+pub const CREATE_TRACK_SESSIONS_TABLE: &str = "
+    CREATE TABLE IF NOT EXISTS track_sessions (
+        track_id INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        sport TEXT NOT NULL,
+        sub_sport TEXT,
+        start_time TEXT,
+        PRIMARY KEY (track_id, position)
+    );
+";
+
+pub const INSERT_TRACK_SESSION: &str = "
+    INSERT INTO track_sessions (track_id, position, sport, sub_sport, start_time)
+    VALUES (?1, ?2, ?3, ?4, ?5);
+";
+
+pub const DELETE_TRACK_SESSIONS: &str = "
+    DELETE FROM track_sessions WHERE track_id = ?1;
+";
+
+pub const GET_TRACKS_WITHOUT_INFERRED_ACTIVITY_TYPE: &str = "
+    SELECT id, filename FROM tracks
+    WHERE is_empty_track IS FALSE AND inferred_activity_type IS NULL;
 ";
 
+pub const UPDATE_ELEVATION_METRICS: &str = "
+UPDATE tracks
+SET
+    elevation_gain = ?2,
+    elevation_loss = ?3,
+    min_altitude = ?4,
+    max_altitude = ?5
+WHERE
+    id = ?1;";
+
+// The guessed type ?4 replaces the activity type of the tracks that do not declare one, unless
+// it has been edited
+pub const SET_INFERRED_ACTIVITY_TYPE: &str = "
+UPDATE tracks
+SET
+    inferred_activity_type = ?2,
+    inferred_activity_type_confidence = ?3,
+    activity_type = CASE
+        WHEN ?4 IS NOT NULL AND raw_activity_type = '' AND activity_type_edited IS FALSE
+        THEN COALESCE((SELECT m.activity_type FROM activity_type_mappings m WHERE m.raw_type = ?4), ?4)
+        ELSE activity_type
+    END
+WHERE
+    id = ?1 AND is_empty_track IS FALSE;";
+
+pub const REGISTER_TRACK_ACTIVITY_TYPE: &str = "
+    INSERT OR IGNORE INTO activity_types (name)
+    SELECT activity_type FROM tracks WHERE id = ?1 AND activity_type != '';
+";
+
+pub const GET_TRACK_FILE_HASHES: &str = "
+    SELECT filename, COALESCE(file_hash, '') FROM tracks WHERE is_empty_track IS FALSE;
+";
+
+// Empty tracks can not be opened, so they are not reported as the stored copy of a file
+pub const GET_TRACK_BY_FILE_HASH: &str = "
+    SELECT id, filename FROM tracks WHERE file_hash = ?1 AND is_empty_track IS FALSE LIMIT 1;
+";
+
+// Empty tracks too, as files with only routes and waypoints are stored as empty tracks
+pub const DELETE_TRACK: &str = "
+    DELETE FROM tracks WHERE id = ?1;
+";
+
+pub const GET_EMPTY_TRACK_FILENAME: &str = "
+    SELECT filename FROM tracks WHERE id = ?1 AND is_empty_track IS TRUE;
+";
+
+// Fields that are not provided are kept. An empty name removes it.
+pub const UPDATE_TRACK: &str = "
+UPDATE tracks
+SET
+    activity_type = COALESCE(?2, activity_type),
+    activity_type_edited = activity_type_edited OR ?2 IS NOT NULL,
+    name = CASE WHEN ?3 IS NULL THEN name ELSE NULLIF(?3, '') END
+WHERE
+    id = ?1 AND is_empty_track IS FALSE;";
+
+pub const GET_TRACK_SUMMARIES: &str = "
+SELECT
+    t.id,
+    t.filename,
+    t.date,
+    t.activity_type,
+    t.north_west_latitude,
+    t.north_west_longitude,
+    t.south_east_latitude,
+    t.south_east_longitude,
+    t.distance,
+    t.elapsed_time,
+    t.moving_time,
+    t.average_speed,
+    t.max_speed,
+    t.elevation_gain,
+    t.elevation_loss,
+    t.min_altitude,
+    t.max_altitude,
+    t.name,
+    t.raw_activity_type,
+    t.inferred_activity_type,
+    t.inferred_activity_type_confidence,
+    t.sport,
+    t.sub_sport,
+    (
+        SELECT json_group_array(
+            json_object('sport', s.sport, 'subSport', s.sub_sport, 'startTime', s.start_time)
+        )
+        FROM (SELECT * FROM track_sessions WHERE track_id = t.id ORDER BY position) s
+    ),
+    t.is_untimed
+FROM tracks t
+WHERE
+    t.is_empty_track IS FALSE";
+
+// Tracks of the activity type or any of its subtypes
+pub const FILTER_ACTIVITY_TYPE_WITH_SUBTYPES: &str = "
+    AND t.activity_type IN (
+        WITH RECURSIVE subtypes(name) AS (
+            SELECT :activity_type
+            UNION
+            SELECT a.name FROM activity_types a JOIN subtypes s ON a.parent = s.name
+        )
+        SELECT name FROM subtypes
+    )";
+
 pub const GET_ALL_ACTIVITY_TYPES: &str = "
 SELECT DISTINCT
     t.activity_type 
@@ -52,52 +206,33 @@ FROM tracks t
 WHERE
     t.activity_type != '' ORDER BY 1;";
 
-pub const GET_TRACKS_INSIDE_LOCATION: &str = "SELECT 
-	filename
-FROM 
-	tracks t 
-WHERE 
-    is_empty_track IS FALSE
-    AND
-(
-    /* Check provided north west is contained inside one track limits*/
-    (
-        :provided_north_west_latitude<= t.north_west_latitude AND  :provided_north_west_latitude >= t.south_east_latitude AND
-        :provided_north_west_longitude >= t.north_west_longitude AND :provided_north_west_longitude <= t.south_east_longitude
-    )
-    /* Check provided limits contain track north west*/
-    OR (
-        t.north_west_latitude <= :provided_north_west_latitude AND t.north_west_latitude >= :provided_south_east_latitude AND
-        t.north_west_longitude >= :provided_north_west_longitude AND t.north_west_longitude  <= :provided_south_east_longitude
-    )
-    /* Check provided south west is contained inside one track limits*/
-    OR (
-        :provided_south_east_latitude<= t.north_west_latitude AND  :provided_south_east_latitude >= t.south_east_latitude AND
-        :provided_north_west_longitude >= t.north_west_longitude AND :provided_north_west_longitude <= t.south_east_longitude
-    )
-    /* Check provided limits contain track south west*/
-    OR (
-        t.south_east_latitude <= :provided_north_west_latitude AND t.south_east_latitude >= :provided_south_east_latitude AND
-        t.north_west_longitude >= :provided_north_west_longitude AND t.north_west_longitude  <= :provided_south_east_longitude
-    )
-    /* Check provided north east is contained inside one track limits*/
-    OR(
-        :provided_north_west_latitude<= t.north_west_latitude AND  :provided_north_west_latitude >= t.south_east_latitude AND
-        :provided_south_east_longitude >= t.north_west_longitude AND :provided_south_east_longitude <= t.south_east_longitude
-    )
-    /* Check provided limits contain track north east*/
-    OR (
-        t.north_west_latitude <= :provided_north_west_latitude AND t.north_west_latitude >= :provided_south_east_latitude AND
-        t.south_east_longitude >= :provided_north_west_longitude AND t.south_east_longitude  <= :provided_south_east_longitude
-    )
-    /* Check provided south east is contained inside one track limits*/
-    OR (
-        :provided_south_east_latitude<= t.north_west_latitude AND  :provided_south_east_latitude >= t.south_east_latitude AND
-        :provided_south_east_longitude >= t.north_west_longitude AND :provided_south_east_longitude <= t.south_east_longitude
-    )
-    /* Check provided limits contain track south east*/
-    OR (
-        t.south_east_latitude <= :provided_north_west_latitude AND t.south_east_latitude >= :provided_south_east_latitude AND
-        t.south_east_longitude >= :provided_north_west_longitude AND t.south_east_longitude  <= :provided_south_east_longitude
-    )
-)";
\ No newline at end of file
+pub const GET_TRACK_REFERENCES: &str = "
+SELECT
+    t.id,
+    t.filename
+FROM tracks t
+WHERE
+    t.is_empty_track IS FALSE";
+
+// The grouping expression is replaced in the query as it can not be bound as a parameter
+pub const GET_TRACK_STATISTICS: &str = "
+SELECT
+    {group_by} AS key,
+    COUNT(*),
+    TOTAL(t.distance),
+    TOTAL(t.elapsed_time),
+    TOTAL(t.moving_time),
+    TOTAL(t.elevation_gain),
+    TOTAL(CASE WHEN t.is_untimed THEN 0 ELSE t.distance END),
+    COUNT(*) FILTER (WHERE t.is_untimed)
+FROM tracks t
+WHERE
+    t.is_empty_track IS FALSE
+GROUP BY key
+ORDER BY key;";
+
+pub const STATISTICS_TOTAL: &str = "'total'";
+pub const STATISTICS_BY_YEAR: &str = "strftime('%Y', t.date)";
+pub const STATISTICS_BY_MONTH: &str = "strftime('%Y-%m', t.date)";
+pub const STATISTICS_BY_WEEK: &str = "strftime('%G-W%V', t.date)";
+pub const STATISTICS_BY_ACTIVITY_TYPE: &str = "t.activity_type";
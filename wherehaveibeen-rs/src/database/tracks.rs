@@ -1,12 +1,245 @@
-use rusqlite::{named_params, params, Connection, Result};
+use rusqlite::{
+    named_params, params, types::Type, Connection, OptionalExtension, Result, Row, ToSql,
+};
+
+use std::collections::HashMap;
 
-use crate::{model::track::TrackInformation, utils::environment::get_database_path};
+use crate::{
+    model::{
+        coordinate::StringifiedCoordinate,
+        filter::{Bounds, SortOrder, TrackCursor, TrackFilter, TrackListOptions, TrackSortField},
+        layer::FileLayers,
+        stats::{Statistics, StatisticsGroup},
+        track::{
+            get_sessions_sport, ActivityInference, TrackInformation, TrackList, TrackMetrics,
+            TrackReference, TrackSession, TrackSummary,
+        },
+    },
+    utils::config::get_database_path,
+};
 
+use super::heatmap::{add_to_heatmap, subtract_from_heatmap};
+use super::layers::{delete_file_layers, insert_file_layers};
+use super::metadata::increment_generation;
+use super::query::activity_type::REGISTER_ACTIVITY_TYPE;
 use super::query::track::{
-    CREATE_TRACKS_TABLE, CREATE_TRACK_COORDINATES_INDEX, CREATE_TRACK_FILENAME_INDEX,
-    GET_ALL_ACTIVITY_TYPES, GET_ALL_TRACK_FILENAMES, GET_TRACKS_INSIDE_LOCATION, INSERT_TRACK,
+    CREATE_TRACKS_TABLE, CREATE_TRACK_COORDINATES_INDEX, CREATE_TRACK_FILENAME_INDEX, DELETE_TRACK,
+    DELETE_TRACK_SESSIONS, FILTER_ACTIVITY_TYPE_WITH_SUBTYPES, GET_ALL_ACTIVITY_TYPES,
+    GET_ALL_TRACK_FILENAMES, GET_EMPTY_TRACK_FILENAME, GET_TRACKS_WITHOUT_INFERRED_ACTIVITY_TYPE,
+    GET_TRACK_BY_FILE_HASH, GET_TRACK_FILENAME, GET_TRACK_FILE_HASHES, GET_TRACK_REFERENCES,
+    GET_TRACK_STATISTICS, GET_TRACK_SUMMARIES, INSERT_TRACK, INSERT_TRACK_SESSION,
+    REGISTER_TRACK_ACTIVITY_TYPE, SET_INFERRED_ACTIVITY_TYPE, STATISTICS_BY_ACTIVITY_TYPE,
+    STATISTICS_BY_MONTH, STATISTICS_BY_WEEK, STATISTICS_BY_YEAR, STATISTICS_TOTAL,
+    UPDATE_ELEVATION_METRICS, UPDATE_TRACK,
 };
 
+type NamedParams = Vec<(&'static str, Box<dyn ToSql>)>;
+
+// Condition and parameters of each of the longitude ranges of an area
+const LONGITUDE_RANGE_CONDITIONS: [(&str, &str, &str); 2] = [
+    (
+        "(t.south_east_longitude >= :west_longitude AND t.north_west_longitude <= :east_longitude)",
+        ":west_longitude",
+        ":east_longitude",
+    ),
+    (
+        "(t.south_east_longitude >= :antimeridian_west_longitude
+            AND t.north_west_longitude <= :antimeridian_east_longitude)",
+        ":antimeridian_west_longitude",
+        ":antimeridian_east_longitude",
+    ),
+];
+
+fn read_track_summary(row: &Row) -> Result<TrackSummary> {
+    Ok(TrackSummary {
+        id: row.get(0)?,
+        filename: row.get(1)?,
+        date: row.get(2)?,
+        activity_type: row.get(3)?,
+        bounds: Bounds {
+            north_west_latitude: row.get(4)?,
+            north_west_longitude: row.get(5)?,
+            south_east_latitude: row.get(6)?,
+            south_east_longitude: row.get(7)?,
+        },
+        metrics: TrackMetrics {
+            distance: row.get(8)?,
+            elapsed_time: row.get(9)?,
+            moving_time: row.get(10)?,
+            average_speed: row.get(11)?,
+            max_speed: row.get(12)?,
+            elevation_gain: row.get(13)?,
+            elevation_loss: row.get(14)?,
+            min_altitude: row.get(15)?,
+            max_altitude: row.get(16)?,
+        },
+        name: row.get(17)?,
+        raw_activity_type: row.get(18)?,
+        inferred_activity_type: row.get(19)?,
+        inferred_activity_type_confidence: row.get(20)?,
+        sport: row.get(21)?,
+        sub_sport: row.get(22)?,
+        sessions: read_sessions(row, 23)?,
+        untimed: row.get(24)?,
+    })
+}
+
+// The sessions are aggregated as a JSON array in the query
+fn read_sessions(row: &Row, index: usize) -> Result<Vec<TrackSession>> {
+    let sessions: String = row.get(index)?;
+
+    serde_json::from_str(&sessions)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+// Append the conditions of the filter to a query that already has a WHERE clause
+fn get_filter_conditions(query: &mut String, filter: &TrackFilter) -> NamedParams {
+    let mut params: NamedParams = Vec::new();
+
+    if let Some(bounds) = filter.bounds {
+        // The bounding box of the track intersects the provided one. An area crossing the
+        // antimeridian is split in two longitude ranges.
+        query.push_str(
+            " AND t.north_west_latitude >= :south_east_latitude
+            AND t.south_east_latitude <= :north_west_latitude",
+        );
+        params.push((":north_west_latitude", Box::new(bounds.north_west_latitude)));
+        params.push((":south_east_latitude", Box::new(bounds.south_east_latitude)));
+
+        let ranges = bounds.longitude_ranges();
+        let conditions: Vec<&str> = LONGITUDE_RANGE_CONDITIONS[..ranges.len()]
+            .iter()
+            .map(|(condition, _, _)| *condition)
+            .collect();
+        query.push_str(&format!(" AND ({})", conditions.join(" OR ")));
+        for ((_, west, east), (west_longitude, east_longitude)) in
+            LONGITUDE_RANGE_CONDITIONS.iter().zip(ranges)
+        {
+            params.push((west, Box::new(west_longitude)));
+            params.push((east, Box::new(east_longitude)));
+        }
+    }
+    if let Some(activity_type) = &filter.activity_type {
+        query.push_str(FILTER_ACTIVITY_TYPE_WITH_SUBTYPES);
+        params.push((":activity_type", Box::new(activity_type.clone())));
+    }
+    // Dates are compared by prefix so that `2023` as end date includes the whole year
+    if let Some(start_date) = &filter.start_date {
+        query.push_str(" AND substr(t.date, 1, length(:start_date)) >= :start_date");
+        params.push((":start_date", Box::new(start_date.clone())));
+    }
+    if let Some(end_date) = &filter.end_date {
+        query.push_str(" AND substr(t.date, 1, length(:end_date)) <= :end_date");
+        params.push((":end_date", Box::new(end_date.clone())));
+    }
+    if let Some(min_distance) = filter.min_distance {
+        query.push_str(" AND t.distance >= :min_distance");
+        params.push((":min_distance", Box::new(min_distance)));
+    }
+    if let Some(max_distance) = filter.max_distance {
+        query.push_str(" AND t.distance <= :max_distance");
+        params.push((":max_distance", Box::new(max_distance)));
+    }
+
+    params
+}
+
+fn as_named_params(params: &NamedParams) -> Vec<(&str, &dyn ToSql)> {
+    params
+        .iter()
+        .map(|(name, value)| (*name, value.as_ref()))
+        .collect()
+}
+
+// Column used to sort the tracks and whether it contains numbers
+fn get_sort_column(sort_by: TrackSortField) -> (&'static str, bool) {
+    match sort_by {
+        TrackSortField::Date => ("t.date", false),
+        TrackSortField::Distance => ("t.distance", true),
+        TrackSortField::ElapsedTime => ("t.elapsed_time", true),
+        TrackSortField::MovingTime => ("t.moving_time", true),
+        TrackSortField::ElevationGain => ("COALESCE(t.elevation_gain, 0)", true),
+        TrackSortField::Filename => ("t.filename", false),
+        TrackSortField::ActivityType => ("t.activity_type", false),
+    }
+}
+
+fn get_sort_value(track: &TrackSummary, sort_by: TrackSortField) -> String {
+    match sort_by {
+        TrackSortField::Date => track.date.clone(),
+        TrackSortField::Distance => track.metrics.distance.to_string(),
+        TrackSortField::ElapsedTime => track.metrics.elapsed_time.to_string(),
+        TrackSortField::MovingTime => track.metrics.moving_time.to_string(),
+        TrackSortField::ElevationGain => track.metrics.elevation_gain.unwrap_or(0.0).to_string(),
+        TrackSortField::Filename => track.filename.clone(),
+        TrackSortField::ActivityType => track.activity_type.clone(),
+    }
+}
+
+// It takes a connection so that it can be part of the transaction storing the whole track
+fn insert_track_row(
+    conn: &Connection,
+    filename: &str,
+    track_information: TrackInformation,
+    is_empty_track: bool,
+    file_hash: &str,
+) -> Result<i64, rusqlite::Error> {
+    // Empty tracks have no activity type
+    let activity_type = match is_empty_track {
+        true => "",
+        false => track_information.get_stored_activity_type(),
+    };
+    let inference = track_information.inferred_activity_type.as_ref();
+    let sport = get_sessions_sport(&track_information.sessions);
+    conn.execute(
+        INSERT_TRACK,
+        params![
+            filename,
+            track_information.north_west_latitude,
+            track_information.north_west_longitude,
+            track_information.south_east_latitude,
+            track_information.south_east_longitude,
+            is_empty_track,
+            track_information.date,
+            activity_type,
+            track_information.metrics.distance,
+            track_information.metrics.elapsed_time,
+            track_information.metrics.moving_time,
+            track_information.metrics.average_speed,
+            track_information.metrics.max_speed,
+            track_information.metrics.elevation_gain,
+            track_information.metrics.elevation_loss,
+            track_information.metrics.min_altitude,
+            track_information.metrics.max_altitude,
+            file_hash,
+            track_information.activity_type,
+            inference.map(|inference| &inference.activity_type),
+            inference.map(|inference| inference.confidence),
+            sport.map(|(sport, _)| sport),
+            sport.and_then(|(_, sub_sport)| sub_sport),
+            track_information.untimed
+        ],
+    )?;
+    let id = conn.last_insert_rowid();
+    for (position, session) in track_information.sessions.iter().enumerate() {
+        conn.execute(
+            INSERT_TRACK_SESSION,
+            params![
+                id,
+                position,
+                session.sport,
+                session.sub_sport,
+                session.start_time
+            ],
+        )?;
+    }
+    if !is_empty_track {
+        conn.execute(REGISTER_TRACK_ACTIVITY_TYPE, params![id])?;
+    }
+
+    Ok(id)
+}
+
 pub struct TracksDatabase {
     pub conn: Connection,
 }
@@ -26,7 +259,7 @@ impl TracksDatabase {
     pub fn get_all_filenames(&self) -> Vec<String> {
         let mut select_statement = self.conn.prepare(GET_ALL_TRACK_FILENAMES).unwrap();
         let rows = select_statement
-            .query_map([], |row| Ok(row.get::<_, String>(0)?))
+            .query_map([], |row| row.get::<_, String>(0))
             .unwrap();
         let mut files: Vec<String> = Vec::new();
         for filename in rows {
@@ -64,67 +297,148 @@ impl TracksDatabase {
         filename: &str,
         track_information: TrackInformation,
         is_empty_track: bool,
-    ) -> Result<(), rusqlite::Error> {
+        file_hash: &str,
+    ) -> Result<i64, rusqlite::Error> {
+        insert_track_row(
+            &self.conn,
+            filename,
+            track_information,
+            is_empty_track,
+            file_hash,
+        )
+    }
+
+    /// Store a track with its sessions, the routes and waypoints of its file and its contribution
+    /// to the heatmap in a single transaction, so nothing is kept if any of them fails.
+    pub fn store_track(
+        &mut self,
+        filename: &str,
+        track_information: TrackInformation,
+        file_hash: &str,
+        layers: &FileLayers,
+        heatmap: &HashMap<StringifiedCoordinate, i32>,
+    ) -> Result<i64> {
+        let tx = self.conn.transaction()?;
+        let id = insert_track_row(&tx, filename, track_information, false, file_hash)?;
+        insert_file_layers(&tx, filename, layers)?;
+        add_to_heatmap(&tx, heatmap)?;
+        increment_generation(&tx)?;
+        tx.commit()?;
+
+        Ok(id)
+    }
+
+    /// Tracks stored before the activity types were guessed
+    pub fn get_tracks_without_inferred_activity_type(&self) -> Result<Vec<TrackReference>> {
+        let mut stmt = self
+            .conn
+            .prepare(GET_TRACKS_WITHOUT_INFERRED_ACTIVITY_TYPE)?;
+        let tracks = stmt.query_map([], |row| {
+            Ok(TrackReference {
+                id: row.get(0)?,
+                filename: row.get(1)?,
+            })
+        })?;
+
+        tracks.collect()
+    }
+
+    /// Store the guessed activity type. It also becomes the activity type of the track if the
+    /// file does not declare one and it has not been edited.
+    pub fn set_inferred_activity_type(&self, id: i64, inference: &ActivityInference) -> Result<()> {
+        let activity_type = inference.is_reliable().then_some(&inference.activity_type);
         self.conn.execute(
-            INSERT_TRACK,
+            SET_INFERRED_ACTIVITY_TYPE,
             params![
-                filename,
-                track_information.north_west_latitude,
-                track_information.north_west_longitude,
-                track_information.south_east_latitude,
-                track_information.south_east_longitude,
-                is_empty_track,
-                track_information.date,
-                track_information.activity_type
+                id,
+                inference.activity_type,
+                inference.confidence,
+                activity_type
             ],
         )?;
+        self.conn
+            .execute(REGISTER_TRACK_ACTIVITY_TYPE, params![id])?;
 
         Ok(())
     }
 
-    pub fn get_tracks_inside_location(
-        &self,
-        track_information: TrackInformation,
-    ) -> Result<Vec<String>> {
-        let mut query = String::from(GET_TRACKS_INSIDE_LOCATION);
-
-        if !track_information.activity_type.is_empty() {
-            query.push_str(" AND t.activity_type = :activity_type");
-        } else {
-            // TODO: Solve this in a cleaner way. named_params does not allow conditionally adding filters
-            query.push_str(
-                " AND (t.activity_type = :activity_type OR t.activity_type != :activity_type)",
-            );
-        }
+    /// Store the climbs computed again from corrected elevations
+    pub fn update_elevation_metrics(&self, id: i64, metrics: &TrackMetrics) -> Result<()> {
+        self.conn.execute(
+            UPDATE_ELEVATION_METRICS,
+            params![
+                id,
+                metrics.elevation_gain,
+                metrics.elevation_loss,
+                metrics.min_altitude,
+                metrics.max_altitude
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Track stored from a file with exactly the same content, if any
+    pub fn get_track_by_file_hash(&self, file_hash: &str) -> Result<Option<TrackReference>> {
+        self.conn
+            .query_row(GET_TRACK_BY_FILE_HASH, params![file_hash], |row| {
+                Ok(TrackReference {
+                    id: row.get(0)?,
+                    filename: row.get(1)?,
+                })
+            })
+            .optional()
+    }
+
+    /// Filename and hash of the content of every stored track. The hash is empty if it is unknown.
+    pub fn get_file_hashes(&self) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare(GET_TRACK_FILE_HASHES)?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        rows.collect()
+    }
+
+    pub fn get_filtered_tracks(&self, filter: &TrackFilter) -> Result<Vec<TrackReference>> {
+        let mut query = String::from(GET_TRACK_REFERENCES);
+        let params = get_filter_conditions(&mut query, filter);
+        query.push_str(" ORDER BY t.date");
 
         let mut stmt = self.conn.prepare(&query)?;
+        let named_params = as_named_params(&params);
+        let tracks = stmt.query_map(named_params.as_slice(), |row| {
+            Ok(TrackReference {
+                id: row.get(0)?,
+                filename: row.get(1)?,
+            })
+        })?;
 
-        let params = named_params! {
-            ":provided_north_west_latitude": track_information.north_west_latitude,
-            ":provided_north_west_longitude": track_information.north_west_longitude,
-            ":provided_south_east_latitude": track_information.south_east_latitude,
-            ":provided_south_east_longitude": track_information.south_east_longitude,
-            ":activity_type": track_information.activity_type
-        };
+        tracks.collect()
+    }
 
-        let filenames = stmt
-            .query_map(params, |row| Ok(row.get::<_, String>(0)?))
-            .unwrap();
+    pub fn get_filtered_track_summaries(&self, filter: &TrackFilter) -> Result<Vec<TrackSummary>> {
+        let mut query = String::from(GET_TRACK_SUMMARIES);
+        let params = get_filter_conditions(&mut query, filter);
+        query.push_str(" ORDER BY t.date");
 
-        let mut files = Vec::new();
-        for filename in filenames {
-            match filename {
-                Ok(f) => {
-                    files.push(f);
-                }
-                Err(e) => {
-                    eprintln!("Error retrieving filename: {}", e);
-                }
-            }
-        }
-        dbg!(&files);
+        let mut stmt = self.conn.prepare(&query)?;
+        let named_params = as_named_params(&params);
+        let tracks = stmt.query_map(named_params.as_slice(), read_track_summary)?;
 
-        Ok(files)
+        tracks.collect()
+    }
+
+    pub fn get_track_filename(&self, id: i64) -> Result<Option<String>> {
+        self.conn
+            .query_row(GET_TRACK_FILENAME, params![id], |row| row.get(0))
+            .optional()
+    }
+
+    /// Id of the row of a file that has no track, like a GPX file with only routes and waypoints
+    /// Filename of a file without track, like a GPX file with only routes and waypoints
+    pub fn get_empty_track_filename(&self, id: i64) -> Result<Option<String>> {
+        self.conn
+            .query_row(GET_EMPTY_TRACK_FILENAME, params![id], |row| row.get(0))
+            .optional()
     }
 
     pub fn get_all_activity_types(&self) -> Result<Vec<String>> {
@@ -132,9 +446,7 @@ impl TracksDatabase {
 
         let mut stmt = self.conn.prepare(&query)?;
 
-        let activities = stmt
-            .query_map((), |row| Ok(row.get::<_, String>(0)?))
-            .unwrap();
+        let activities = stmt.query_map((), |row| row.get::<_, String>(0)).unwrap();
 
         let mut activity_types = Vec::new();
         for activity in activities {
@@ -147,8 +459,436 @@ impl TracksDatabase {
                 }
             }
         }
-        dbg!(&activity_types);
 
         Ok(activity_types)
     }
+
+    pub fn get_track_summary(&self, id: i64) -> Result<Option<TrackSummary>> {
+        let mut query = String::from(GET_TRACK_SUMMARIES);
+        query.push_str(" AND t.id = :id");
+
+        self.conn
+            .query_row(&query, named_params! {":id": id}, read_track_summary)
+            .optional()
+    }
+
+    /// Remove the track with its sessions, the routes and waypoints of its file and its
+    /// contribution to the heatmap in a single transaction. Returns false if there is no track
+    /// with the given id, in which case nothing is changed.
+    pub fn delete_track(
+        &mut self,
+        id: i64,
+        filename: &str,
+        heatmap: &HashMap<StringifiedCoordinate, i32>,
+    ) -> Result<bool> {
+        let tx = self.conn.transaction()?;
+        if tx.execute(DELETE_TRACK, params![id])? == 0 {
+            return Ok(false);
+        }
+        tx.execute(DELETE_TRACK_SESSIONS, params![id])?;
+        delete_file_layers(&tx, filename)?;
+        subtract_from_heatmap(&tx, heatmap)?;
+        increment_generation(&tx)?;
+        tx.commit()?;
+
+        Ok(true)
+    }
+
+    /// Returns false if there is no track with the given id
+    pub fn update_track(
+        &self,
+        id: i64,
+        activity_type: Option<&str>,
+        name: Option<&str>,
+    ) -> Result<bool> {
+        let updated_rows = self
+            .conn
+            .execute(UPDATE_TRACK, params![id, activity_type, name])?;
+        if let (true, Some(activity_type)) = (updated_rows > 0, activity_type) {
+            self.conn
+                .execute(REGISTER_ACTIVITY_TYPE, params![activity_type])?;
+        }
+
+        Ok(updated_rows > 0)
+    }
+
+    pub fn get_track_list(
+        &self,
+        filter: &TrackFilter,
+        options: &TrackListOptions,
+    ) -> Result<TrackList> {
+        let mut query = String::from(GET_TRACK_SUMMARIES);
+        let mut params = get_filter_conditions(&mut query, filter);
+
+        let (sort_column, is_numeric) = get_sort_column(options.sort_by);
+        let (comparison, direction) = match options.order {
+            SortOrder::Ascending => (">", "ASC"),
+            SortOrder::Descending => ("<", "DESC"),
+        };
+
+        if let Some(cursor) = &options.cursor {
+            query.push_str(&format!(
+                " AND ({column} {comparison} :cursor_value OR ({column} = :cursor_value AND t.id {comparison} :cursor_id))",
+                column = sort_column,
+            ));
+            // Numbers must be bound as numbers so that the comparison is numeric and exact
+            let cursor_value: Box<dyn ToSql> = match (is_numeric, cursor.sort_value.parse::<f64>())
+            {
+                (true, Ok(number)) => Box::new(number),
+                _ => Box::new(cursor.sort_value.clone()),
+            };
+            params.push((":cursor_value", cursor_value));
+            params.push((":cursor_id", Box::new(cursor.id)));
+        }
+
+        query.push_str(&format!(
+            " ORDER BY {sort_column} {direction}, t.id {direction} LIMIT :limit"
+        ));
+        // Request one extra row to know if there is a next page
+        params.push((":limit", Box::new(options.limit as i64 + 1)));
+
+        let mut stmt = self.conn.prepare(&query)?;
+        let named_params = as_named_params(&params);
+        let mut tracks = stmt
+            .query_map(named_params.as_slice(), read_track_summary)?
+            .collect::<Result<Vec<TrackSummary>>>()?;
+
+        let mut next_cursor = None;
+        if tracks.len() > options.limit {
+            tracks.truncate(options.limit);
+            if let Some(last) = tracks.last() {
+                let sort_value = get_sort_value(last, options.sort_by);
+                next_cursor = Some(TrackCursor::new(sort_value, last.id).encode());
+            }
+        }
+
+        Ok(TrackList {
+            tracks,
+            next_cursor,
+        })
+    }
+
+    fn get_statistics_grouped_by(&self, group_by: &str) -> Result<Vec<StatisticsGroup>> {
+        let query = GET_TRACK_STATISTICS.replace("{group_by}", group_by);
+        let mut stmt = self.conn.prepare(&query)?;
+
+        let groups = stmt.query_map([], |row| {
+            Ok(StatisticsGroup {
+                key: row.get(0)?,
+                count: row.get(1)?,
+                distance: row.get(2)?,
+                elapsed_time: row.get(3)?,
+                moving_time: row.get(4)?,
+                elevation_gain: row.get(5)?,
+                timed_distance: row.get(6)?,
+                untimed_count: row.get(7)?,
+            })
+        })?;
+
+        groups.collect()
+    }
+
+    pub fn get_statistics(&self) -> Result<Statistics> {
+        let total = self
+            .get_statistics_grouped_by(STATISTICS_TOTAL)?
+            .pop()
+            .unwrap_or(StatisticsGroup {
+                key: "total".to_string(),
+                count: 0,
+                distance: 0.0,
+                elapsed_time: 0.0,
+                moving_time: 0.0,
+                elevation_gain: 0.0,
+                timed_distance: 0.0,
+                untimed_count: 0,
+            });
+
+        Ok(Statistics {
+            total,
+            by_year: self.get_statistics_grouped_by(STATISTICS_BY_YEAR)?,
+            by_month: self.get_statistics_grouped_by(STATISTICS_BY_MONTH)?,
+            by_week: self.get_statistics_grouped_by(STATISTICS_BY_WEEK)?,
+            by_activity_type: self.get_statistics_grouped_by(STATISTICS_BY_ACTIVITY_TYPE)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::{
+        migrations::run_migrations,
+        query::{
+            heatmap::{
+                CREATE_HEATMAP_TABLE, GET_NR_HEATMAP_ROWS, INSERT_OR_UPDATE_DATA_INTO_HEATMAP,
+            },
+            layer::INSERT_WAYPOINT,
+            metadata::GET_GENERATION,
+        },
+    };
+    use crate::model::layer::Waypoint;
+
+    fn create_database() -> TracksDatabase {
+        let mut tracks_db = TracksDatabase {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        tracks_db.initialize_table().unwrap();
+        tracks_db.conn.execute(CREATE_HEATMAP_TABLE, ()).unwrap();
+        run_migrations(&mut tracks_db.conn).unwrap();
+        tracks_db
+    }
+
+    fn count_rows(tracks_db: &TracksDatabase, query: &str) -> i64 {
+        tracks_db
+            .conn
+            .query_row(query, [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_delete_track() {
+        let mut tracks_db = create_database();
+        let track_information = TrackInformation::new(
+            48.0,
+            11.0,
+            47.0,
+            12.0,
+            "2023-05-01T07:00:00Z".to_string(),
+            "running".to_string(),
+        );
+        let id = tracks_db
+            .insert_new_file("a.gpx", track_information, false, "hash")
+            .unwrap();
+        assert_eq!(
+            tracks_db
+                .get_track_by_file_hash("hash")
+                .unwrap()
+                .map(|track| track.id),
+            Some(id)
+        );
+        tracks_db
+            .conn
+            .execute(
+                INSERT_WAYPOINT,
+                params![
+                    "a.gpx",
+                    "Hut",
+                    None::<String>,
+                    None::<String>,
+                    None::<String>,
+                    47.5,
+                    11.5,
+                    None::<f64>,
+                    None::<String>
+                ],
+            )
+            .unwrap();
+        tracks_db
+            .conn
+            .execute(
+                INSERT_OR_UPDATE_DATA_INTO_HEATMAP,
+                params!["47.50000", "11.50000", 1],
+            )
+            .unwrap();
+
+        let heatmap = HashMap::from([(
+            StringifiedCoordinate::new("47.50000".to_string(), "11.50000".to_string()),
+            1,
+        )]);
+        assert!(!tracks_db.delete_track(id + 1, "b.gpx", &heatmap).unwrap());
+        assert_eq!(count_rows(&tracks_db, GET_NR_HEATMAP_ROWS), 1);
+        assert_eq!(count_rows(&tracks_db, GET_GENERATION), 0);
+
+        assert!(tracks_db.delete_track(id, "a.gpx", &heatmap).unwrap());
+        assert!(tracks_db.get_track_summary(id).unwrap().is_none());
+        assert_eq!(count_rows(&tracks_db, "SELECT COUNT(*) FROM waypoints"), 0);
+        assert_eq!(count_rows(&tracks_db, GET_NR_HEATMAP_ROWS), 0);
+        assert_eq!(count_rows(&tracks_db, GET_GENERATION), 1);
+    }
+
+    #[test]
+    fn test_store_track() {
+        let mut tracks_db = create_database();
+        let track_information = |date: &str| {
+            TrackInformation::new(
+                48.0,
+                11.0,
+                47.0,
+                12.0,
+                date.to_string(),
+                "running".to_string(),
+            )
+        };
+        let layers = FileLayers {
+            routes: Vec::new(),
+            waypoints: vec![Waypoint {
+                id: 0,
+                filename: String::new(),
+                file_id: None,
+                name: Some("Hut".to_string()),
+                description: None,
+                symbol: None,
+                waypoint_type: None,
+                latitude: 47.5,
+                longitude: 11.5,
+                elevation: None,
+                time: None,
+            }],
+        };
+        let heatmap = HashMap::from([(
+            StringifiedCoordinate::new("47.50000".to_string(), "11.50000".to_string()),
+            2,
+        )]);
+
+        let id = tracks_db
+            .store_track(
+                "a.gpx",
+                track_information("2023-05-01T07:00:00Z"),
+                "hash",
+                &layers,
+                &heatmap,
+            )
+            .unwrap();
+        assert!(tracks_db.get_track_summary(id).unwrap().is_some());
+        assert_eq!(count_rows(&tracks_db, "SELECT COUNT(*) FROM waypoints"), 1);
+        assert_eq!(
+            count_rows(&tracks_db, "SELECT SUM(frequency) FROM heatmap"),
+            2
+        );
+        assert_eq!(count_rows(&tracks_db, GET_GENERATION), 1);
+
+        // Nothing is kept when a part of the track can not be stored
+        tracks_db.conn.execute("DROP TABLE waypoints;", ()).unwrap();
+        assert!(tracks_db
+            .store_track(
+                "b.gpx",
+                track_information("2023-05-02T07:00:00Z"),
+                "other-hash",
+                &layers,
+                &heatmap,
+            )
+            .is_err());
+        assert_eq!(count_rows(&tracks_db, "SELECT COUNT(*) FROM tracks"), 1);
+        assert_eq!(
+            count_rows(&tracks_db, "SELECT SUM(frequency) FROM heatmap"),
+            2
+        );
+        assert_eq!(count_rows(&tracks_db, GET_GENERATION), 1);
+    }
+
+    fn insert_track(
+        tracks_db: &TracksDatabase,
+        date: &str,
+        activity_type: &str,
+        distance: f64,
+        untimed: bool,
+    ) {
+        let mut track_information = TrackInformation::new(
+            48.0,
+            11.0,
+            47.0,
+            12.0,
+            date.to_string(),
+            activity_type.to_string(),
+        );
+        track_information.metrics.distance = distance;
+        track_information.metrics.moving_time = if untimed { 0.0 } else { 3600.0 };
+        track_information.untimed = untimed;
+        tracks_db
+            .insert_new_file(date, track_information, false, date)
+            .unwrap();
+    }
+
+    fn get_keys_and_counts(groups: &[StatisticsGroup]) -> Vec<(&str, i64)> {
+        groups
+            .iter()
+            .map(|group| (group.key.as_str(), group.count))
+            .collect()
+    }
+
+    #[test]
+    fn test_get_statistics() {
+        let tracks_db = create_database();
+        insert_track(
+            &tracks_db,
+            "2024-06-15T08:00:00+00:00",
+            "running",
+            5000.0,
+            true,
+        );
+        insert_track(
+            &tracks_db,
+            "2024-12-30T08:00:00+00:00",
+            "running",
+            10000.0,
+            false,
+        );
+        insert_track(
+            &tracks_db,
+            "2025-01-02T08:00:00+00:00",
+            "cycling",
+            30000.0,
+            false,
+        );
+        tracks_db
+            .insert_new_file(
+                "empty.gpx",
+                TrackInformation::create_empty_track(),
+                true,
+                "empty",
+            )
+            .unwrap();
+
+        let statistics = tracks_db.get_statistics().unwrap();
+        assert_eq!(statistics.total.count, 3);
+        assert_eq!(statistics.total.distance, 45000.0);
+        // The untimed track is left out of the distance used for the speed
+        assert_eq!(statistics.total.timed_distance, 40000.0);
+        assert_eq!(statistics.total.untimed_count, 1);
+
+        assert_eq!(
+            get_keys_and_counts(&statistics.by_year),
+            vec![("2024", 2), ("2025", 1)]
+        );
+        assert_eq!(
+            get_keys_and_counts(&statistics.by_month),
+            vec![("2024-06", 1), ("2024-12", 1), ("2025-01", 1)]
+        );
+        // The last days of December 2024 are part of the first ISO week of 2025
+        assert_eq!(
+            get_keys_and_counts(&statistics.by_week),
+            vec![("2024-W24", 1), ("2025-W01", 2)]
+        );
+        assert_eq!(
+            get_keys_and_counts(&statistics.by_activity_type),
+            vec![("cycling", 1), ("running", 2)]
+        );
+        let running = &statistics.by_activity_type[1];
+        assert_eq!(running.distance, 15000.0);
+        assert_eq!(running.timed_distance, 10000.0);
+        assert_eq!(running.untimed_count, 1);
+    }
+
+    #[test]
+    fn test_delete_empty_track() {
+        let mut tracks_db = create_database();
+        let track_information =
+            TrackInformation::new(0.0, 0.0, 0.0, 0.0, String::new(), String::new());
+        let id = tracks_db
+            .insert_new_file("plan.gpx", track_information, true, "hash")
+            .unwrap();
+
+        assert_eq!(tracks_db.get_empty_track_filename(id + 1).unwrap(), None);
+        assert_eq!(
+            tracks_db.get_empty_track_filename(id).unwrap().as_deref(),
+            Some("plan.gpx")
+        );
+        assert!(tracks_db.get_track_summary(id).unwrap().is_none());
+        assert!(tracks_db.get_track_by_file_hash("hash").unwrap().is_none());
+        assert!(tracks_db
+            .delete_track(id, "plan.gpx", &HashMap::new())
+            .unwrap());
+        assert_eq!(tracks_db.get_empty_track_filename(id).unwrap(), None);
+    }
 }
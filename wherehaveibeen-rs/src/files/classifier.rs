@@ -0,0 +1,361 @@
+use crate::{
+    model::{track::ActivityInference, trackpoint::TrackPoint},
+    utils::geo_utils::haversine_distance,
+};
+
+use super::metrics::{get_timestamp, MOVING_SPEED_THRESHOLD};
+
+// Consecutive moving points are grouped in windows of this length (m) so that the noise of the
+// positions and altitudes does not dominate the speed and the slope
+const WINDOW_DISTANCE: f64 = 50.0;
+// Faster points (m/s) are jumps of the position and are ignored
+const MAX_SEGMENT_SPEED: f64 = 100.0;
+// Tracks with fewer windows or less moving time (s) are not classified
+const MIN_WINDOWS: usize = 10;
+const MIN_MOVING_TIME: f64 = 120.0;
+// Below this moving time (s) the confidence is reduced
+const SHORT_MOVING_TIME: f64 = 600.0;
+
+// Median moving speed (m/s) where each activity starts. Walking starts when moving.
+const RUNNING_SPEED: f64 = 2.0;
+const CYCLING_SPEED: f64 = 4.2;
+const DRIVING_SPEED: f64 = 11.0;
+// Above this speed a track is surely not human powered
+const CERTAIN_DRIVING_SPEED: f64 = 20.0;
+// Runners can not keep this speed for 10% of the time, cyclists in the city easily do
+const MAX_RUNNING_FAST_SPEED: f64 = 7.0;
+
+// Strides per minute that separate walking and running
+const RUNNING_CADENCE: f64 = 70.0;
+// Share of the time with cadence where it is above zero. Motors and lifts do not pedal.
+const PEDALING_TIME_SHARE: f64 = 0.5;
+
+// Downhill skiing descends steep slopes fast and goes up with the lifts
+const DESCENDING_GRADE: f64 = -0.08;
+const SKIING_GRADE: f64 = -0.15;
+const SKIING_SPEED: f64 = 4.0;
+const MIN_SKIING_DESCENT: f64 = 300.0;
+// Descent (m) from which the confidence of skiing is maximal
+const CERTAIN_SKIING_DESCENT: f64 = 2000.0;
+
+const MAX_CONFIDENCE: f64 = 0.95;
+
+#[derive(Default)]
+struct Window {
+    // Meters
+    distance: f64,
+    seconds: f64,
+    start_elevation: f64,
+    end_elevation: f64,
+}
+
+impl Window {
+    fn speed(&self) -> f64 {
+        self.distance / self.seconds
+    }
+
+    // Change of altitude per meter. `None` if the points have no altitude.
+    fn grade(&self) -> Option<f64> {
+        let climb = self.end_elevation - self.start_elevation;
+        (!climb.is_nan()).then(|| climb / self.distance)
+    }
+}
+
+#[derive(Default)]
+struct Cadence {
+    // Seconds of movement with a cadence value
+    recorded_time: f64,
+    // Seconds of movement with a cadence above zero
+    pedaling_time: f64,
+    // Cadence above zero weighted by the seconds
+    values: Vec<(f64, f64)>,
+}
+
+impl Cadence {
+    fn is_pedaling(&self) -> bool {
+        self.recorded_time > 0.0 && self.pedaling_time / self.recorded_time >= PEDALING_TIME_SHARE
+    }
+
+    fn median(&self) -> Option<f64> {
+        let mut values = self.values.clone();
+        weighted_percentile(&mut values, 0.5)
+    }
+}
+
+// Value below which the given share of the total weight is found
+fn weighted_percentile(values: &mut [(f64, f64)], share: f64) -> Option<f64> {
+    values.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let total_weight: f64 = values.iter().map(|(_, weight)| weight).sum();
+
+    let mut accumulated_weight = 0.0;
+    for (value, weight) in values.iter() {
+        accumulated_weight += weight;
+        if accumulated_weight >= share * total_weight {
+            return Some(*value);
+        }
+    }
+
+    None
+}
+
+// Windows of continuous movement. Stops and jumps of the position discard the current window.
+fn get_moving_windows(track_points: &[TrackPoint]) -> (Vec<Window>, Cadence) {
+    let mut windows: Vec<Window> = Vec::new();
+    let mut cadence = Cadence::default();
+    let mut window = Window::default();
+
+    for (previous, current) in track_points.iter().zip(track_points.iter().skip(1)) {
+        let (Some(start), Some(end)) = (get_timestamp(previous), get_timestamp(current)) else {
+            continue;
+        };
+        let seconds = (end - start) as f64 / 1000.0;
+        if seconds <= 0.0 {
+            continue;
+        }
+
+        let distance = haversine_distance(
+            previous.latitude,
+            previous.longitude,
+            current.latitude,
+            current.longitude,
+        );
+        let speed = distance / seconds;
+        if !(MOVING_SPEED_THRESHOLD..=MAX_SEGMENT_SPEED).contains(&speed) {
+            window = Window::default();
+            continue;
+        }
+
+        if let Some(value) = current.cadence {
+            cadence.recorded_time += seconds;
+            if value > 0.0 {
+                cadence.pedaling_time += seconds;
+                cadence.values.push((value, seconds));
+            }
+        }
+
+        if window.seconds == 0.0 {
+            window.start_elevation = previous.elevation;
+        }
+        window.distance += distance;
+        window.seconds += seconds;
+        window.end_elevation = current.elevation;
+        if window.distance >= WINDOW_DISTANCE {
+            windows.push(std::mem::take(&mut window));
+        }
+    }
+
+    (windows, cadence)
+}
+
+// 0.5 at the limits of the range of speeds of the activity and higher towards its center
+fn get_speed_confidence(speed: f64, min_speed: f64, max_speed: f64) -> f64 {
+    let half_range = (max_speed - min_speed) / 2.0;
+    let margin = (speed - min_speed).min(max_speed - speed) / half_range;
+
+    0.5 + 0.4 * margin.clamp(0.0, 1.0)
+}
+
+fn infer_skiing(windows: &[Window], cadence: &Cadence) -> Option<f64> {
+    if cadence.is_pedaling() {
+        return None;
+    }
+
+    let mut grades: Vec<(f64, f64)> = Vec::new();
+    let mut speeds: Vec<(f64, f64)> = Vec::new();
+    let mut descent = 0.0;
+    for window in windows {
+        if let Some(grade) = window.grade().filter(|grade| *grade <= DESCENDING_GRADE) {
+            grades.push((grade, window.distance));
+            speeds.push((window.speed(), window.seconds));
+            descent -= grade * window.distance;
+        }
+    }
+    if descent < MIN_SKIING_DESCENT {
+        return None;
+    }
+
+    let grade = weighted_percentile(&mut grades, 0.5)?;
+    let speed = weighted_percentile(&mut speeds, 0.5)?;
+    if grade > SKIING_GRADE || speed < SKIING_SPEED {
+        return None;
+    }
+
+    Some(0.6 + 0.3 * (descent / CERTAIN_SKIING_DESCENT).min(1.0))
+}
+
+fn infer_from_speed(median_speed: f64, fast_speed: f64, cadence: &Cadence) -> (&'static str, f64) {
+    if median_speed >= DRIVING_SPEED {
+        if cadence.is_pedaling() {
+            return ("cycling", 0.6);
+        }
+        let margin = (median_speed - DRIVING_SPEED) / (CERTAIN_DRIVING_SPEED - DRIVING_SPEED);
+        return ("driving", 0.5 + 0.4 * margin.min(1.0));
+    }
+    if median_speed >= CYCLING_SPEED {
+        let confidence = get_speed_confidence(median_speed, CYCLING_SPEED, DRIVING_SPEED);
+        let bonus = if cadence.is_pedaling() { 0.1 } else { 0.0 };
+        return ("cycling", confidence + bonus);
+    }
+    if median_speed >= RUNNING_SPEED && fast_speed > MAX_RUNNING_FAST_SPEED {
+        return ("cycling", 0.5);
+    }
+
+    let (activity_type, confidence) = if median_speed >= RUNNING_SPEED {
+        (
+            "running",
+            get_speed_confidence(median_speed, RUNNING_SPEED, CYCLING_SPEED),
+        )
+    } else {
+        (
+            "walking",
+            get_speed_confidence(median_speed, MOVING_SPEED_THRESHOLD, RUNNING_SPEED),
+        )
+    };
+
+    // The stride rate settles the cases close to the limit between walking and running
+    match cadence.median() {
+        Some(stride_rate) => {
+            let stride_type = if stride_rate >= RUNNING_CADENCE {
+                "running"
+            } else {
+                "walking"
+            };
+            if stride_type == activity_type {
+                (activity_type, confidence + 0.1)
+            } else {
+                (stride_type, 0.6)
+            }
+        }
+        None => (activity_type, confidence),
+    }
+}
+
+/// Guess the activity from the speed, cadence and slopes of the points. `None` if the track does
+/// not have enough timed points.
+pub fn infer_activity_type(track_points: &[TrackPoint]) -> Option<ActivityInference> {
+    let (windows, cadence) = get_moving_windows(track_points);
+    let moving_time: f64 = windows.iter().map(|window| window.seconds).sum();
+    if windows.len() < MIN_WINDOWS || moving_time < MIN_MOVING_TIME {
+        return None;
+    }
+
+    let (activity_type, mut confidence) = match infer_skiing(&windows, &cadence) {
+        Some(confidence) => ("skiing", confidence),
+        None => {
+            let mut speeds: Vec<(f64, f64)> = windows
+                .iter()
+                .map(|window| (window.speed(), window.seconds))
+                .collect();
+            let median_speed = weighted_percentile(&mut speeds, 0.5)?;
+            let fast_speed = weighted_percentile(&mut speeds, 0.9)?;
+            infer_from_speed(median_speed, fast_speed, &cadence)
+        }
+    };
+
+    if moving_time < SHORT_MOVING_TIME {
+        confidence *= 0.85;
+    }
+
+    Some(ActivityInference {
+        activity_type: activity_type.to_string(),
+        confidence: (confidence.min(MAX_CONFIDENCE) * 100.0).round() / 100.0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::{DateTime, Duration};
+
+    // Every 0.001 degrees of latitude are around 111 meters
+    const METERS_PER_DEGREE: f64 = 111_195.0;
+
+    // Straight line to the north with a point every 5 seconds. Each stage has a speed and a climb
+    // per meter.
+    fn create_track(stages: &[(f64, f64, usize)], cadence: Option<f64>) -> Vec<TrackPoint> {
+        let start = DateTime::parse_from_rfc3339("2024-01-20T09:00:00Z").unwrap();
+        let mut latitude = 47.0;
+        let mut elevation = 1000.0;
+        let mut seconds = 0;
+        let mut track_points = Vec::new();
+
+        for (speed, grade, points) in stages {
+            for _ in 0..*points {
+                let time = start + Duration::seconds(seconds);
+                let mut point = TrackPoint::new(latitude, 11.0, elevation, time.to_rfc3339());
+                point.cadence = cadence;
+                track_points.push(point);
+
+                latitude += speed * 5.0 / METERS_PER_DEGREE;
+                elevation += speed * 5.0 * grade;
+                seconds += 5;
+            }
+        }
+
+        track_points
+    }
+
+    fn infer(stages: &[(f64, f64, usize)], cadence: Option<f64>) -> ActivityInference {
+        infer_activity_type(&create_track(stages, cadence)).unwrap()
+    }
+
+    #[test]
+    fn test_infer_activity_type_from_speed() {
+        let walk = infer(&[(1.3, 0.0, 400)], None);
+        assert_eq!(walk.activity_type, "walking");
+        assert!(walk.is_reliable());
+
+        assert_eq!(infer(&[(3.0, 0.0, 400)], None).activity_type, "running");
+        assert_eq!(infer(&[(7.5, 0.01, 400)], None).activity_type, "cycling");
+
+        let drive = infer(&[(25.0, 0.0, 400)], None);
+        assert_eq!(drive.activity_type, "driving");
+        assert_eq!(drive.confidence, 0.9);
+    }
+
+    #[test]
+    fn test_infer_activity_type_with_cadence() {
+        // A fast road cyclist is not a car when pedaling
+        assert_eq!(infer(&[(12.0, 0.0, 400)], None).activity_type, "driving");
+        assert_eq!(
+            infer(&[(12.0, 0.0, 400)], Some(90.0)).activity_type,
+            "cycling"
+        );
+
+        // Slow running is told apart from fast walking by the stride rate
+        assert_eq!(infer(&[(2.1, 0.0, 400)], None).activity_type, "running");
+        assert_eq!(
+            infer(&[(2.1, 0.0, 400)], Some(55.0)).activity_type,
+            "walking"
+        );
+    }
+
+    #[test]
+    fn test_infer_skiing() {
+        // Lift up and slope down three times
+        let lap = [(3.0, 0.3, 80), (10.0, -0.25, 30)];
+        let stages: Vec<(f64, f64, usize)> = lap.iter().cycle().take(6).copied().collect();
+
+        let ski = infer(&stages, None);
+        assert_eq!(ski.activity_type, "skiing");
+        assert!(ski.is_reliable());
+
+        // A cyclist climbing and going down a mountain road
+        let ride = infer(&[(3.0, 0.07, 300), (12.0, -0.07, 80)], None);
+        assert_eq!(ride.activity_type, "cycling");
+    }
+
+    #[test]
+    fn test_short_tracks_are_not_classified() {
+        assert_eq!(
+            infer_activity_type(&create_track(&[(1.3, 0.0, 5)], None)),
+            None
+        );
+
+        // Points without time
+        let mut track_points = create_track(&[(1.3, 0.0, 400)], None);
+        track_points.iter_mut().for_each(|point| point.time.clear());
+        assert_eq!(infer_activity_type(&track_points), None);
+    }
+}
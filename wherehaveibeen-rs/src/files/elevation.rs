@@ -0,0 +1,174 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use crate::{
+    model::trackpoint::TrackPoint,
+    utils::config::{get_config, get_dem_directory, ElevationCorrection},
+};
+
+// Value of the cells without data, usually water or shadows of the radar
+const NO_DATA: i16 = -32768;
+// A tile of 1 arc second takes 25 MB in memory
+const MAX_LOADED_TILES: usize = 16;
+
+// Tiles that have been read, by the coordinates of their south west corner. `None` when there is
+// no tile or it can not be read.
+type TileCache = HashMap<(i32, i32), Option<Arc<DemTile>>>;
+
+static TILES: OnceLock<Mutex<TileCache>> = OnceLock::new();
+
+/// SRTM tile of one degree. The cells are big endian integers in rows from north to south.
+struct DemTile {
+    size: usize,
+    elevations: Vec<i16>,
+}
+
+impl DemTile {
+    // The size depends on the resolution: 1201 for 3 arc seconds and 3601 for 1 arc second
+    fn parse(content: &[u8]) -> Option<DemTile> {
+        let size = ((content.len() / 2) as f64).sqrt() as usize;
+        if size < 2 || size * size * 2 != content.len() {
+            return None;
+        }
+        let elevations = content
+            .chunks_exact(2)
+            .map(|bytes| i16::from_be_bytes([bytes[0], bytes[1]]))
+            .collect();
+
+        Some(DemTile { size, elevations })
+    }
+
+    fn get_cell(&self, row: usize, column: usize) -> Option<f64> {
+        let elevation = self.elevations[row * self.size + column];
+        (elevation != NO_DATA).then_some(elevation as f64)
+    }
+
+    // Bilinear interpolation of the four cells around the point. The offsets are the fraction of
+    // degree from the south west corner.
+    fn get_elevation(&self, latitude_offset: f64, longitude_offset: f64) -> Option<f64> {
+        let last = (self.size - 1) as f64;
+        let row = (1.0 - latitude_offset) * last;
+        let column = longitude_offset * last;
+        let top = (row.floor() as usize).min(self.size - 2);
+        let left = (column.floor() as usize).min(self.size - 2);
+        let row_fraction = row - top as f64;
+        let column_fraction = column - left as f64;
+
+        let north_west = self.get_cell(top, left)?;
+        let north_east = self.get_cell(top, left + 1)?;
+        let south_west = self.get_cell(top + 1, left)?;
+        let south_east = self.get_cell(top + 1, left + 1)?;
+        let north = north_west + (north_east - north_west) * column_fraction;
+        let south = south_west + (south_east - south_west) * column_fraction;
+
+        Some(north + (south - north) * row_fraction)
+    }
+}
+
+fn get_tile_name(latitude: i32, longitude: i32) -> String {
+    format!(
+        "{}{:02}{}{:03}.hgt",
+        if latitude < 0 { 'S' } else { 'N' },
+        latitude.abs(),
+        if longitude < 0 { 'W' } else { 'E' },
+        longitude.abs()
+    )
+}
+
+fn get_tile(directory: &Path, latitude: i32, longitude: i32) -> Option<Arc<DemTile>> {
+    let mut tiles = TILES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(tile) = tiles.get(&(latitude, longitude)) {
+        return tile.clone();
+    }
+    if tiles.len() >= MAX_LOADED_TILES {
+        tiles.clear();
+    }
+
+    let path = directory.join(get_tile_name(latitude, longitude));
+    let tile = if path.exists() {
+        let tile = std::fs::read(&path)
+            .ok()
+            .and_then(|content| DemTile::parse(&content));
+        if tile.is_none() {
+            eprintln!("Invalid elevation tile {}", path.display());
+        }
+        tile.map(Arc::new)
+    } else {
+        None
+    };
+    tiles.insert((latitude, longitude), tile.clone());
+
+    tile
+}
+
+fn get_dem_elevation(directory: &Path, latitude: f64, longitude: f64) -> Option<f64> {
+    let south = latitude.floor();
+    let west = longitude.floor();
+    let tile = get_tile(directory, south as i32, west as i32)?;
+
+    tile.get_elevation(latitude - south, longitude - west)
+}
+
+/// Take the elevation of the points from the DEM tiles when a directory of tiles is configured.
+/// Points outside of the available tiles keep the elevation of the file. Returns the number of
+/// corrected points.
+pub fn correct_elevations(track_points: &mut [TrackPoint]) -> usize {
+    let Some(directory) = get_dem_directory() else {
+        return 0;
+    };
+    let correction = get_config().elevation.correction;
+
+    let mut corrected_points = 0;
+    for point in track_points {
+        if correction == ElevationCorrection::Fill && !point.elevation.is_nan() {
+            continue;
+        }
+        if let Some(elevation) =
+            get_dem_elevation(Path::new(&directory), point.latitude, point.longitude)
+        {
+            point.elevation = elevation;
+            corrected_points += 1;
+        }
+    }
+
+    corrected_points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_tile(elevations: &[i16]) -> DemTile {
+        let content: Vec<u8> = elevations
+            .iter()
+            .flat_map(|elevation| elevation.to_be_bytes())
+            .collect();
+        DemTile::parse(&content).unwrap()
+    }
+
+    #[test]
+    fn test_get_tile_name() {
+        assert_eq!(get_tile_name(47, 11), "N47E011.hgt");
+        assert_eq!(get_tile_name(-34, -71), "S34W071.hgt");
+    }
+
+    #[test]
+    fn test_get_elevation() {
+        // North row first
+        let tile = create_tile(&[200, 300, 400, 100, 200, 300, 0, 100, NO_DATA]);
+
+        assert_eq!(tile.get_elevation(1.0, 0.0), Some(200.0));
+        assert_eq!(tile.get_elevation(0.5, 0.0), Some(100.0));
+        assert_eq!(tile.get_elevation(0.75, 0.25), Some(200.0));
+        assert_eq!(tile.get_elevation(0.0, 0.0), Some(0.0));
+        assert_eq!(tile.get_elevation(0.25, 0.75), None);
+
+        assert!(DemTile::parse(&[0, 1, 0]).is_none());
+    }
+}
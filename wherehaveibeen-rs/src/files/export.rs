@@ -0,0 +1,172 @@
+use std::{
+    io::{Cursor, Error, Write},
+    path::Path,
+};
+
+use quick_xml::escape::escape;
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::{
+    model::{coordinate::Coordinate, query::ExportFormat, track::TrackSummary},
+    utils::{
+        cache_utils::parse_cached_coordinates,
+        file_access::resolve_path_inside,
+        file_utils::{read_binary_file, read_file},
+    },
+};
+
+fn write_gpx(tracks: &[(TrackSummary, Vec<Coordinate>)]) -> String {
+    let mut content = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <gpx version=\"1.1\" creator=\"wherehaveibeen\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+    );
+
+    for (summary, coordinates) in tracks {
+        content.push_str("  <trk>\n");
+        content.push_str(&format!(
+            "    <name>{}</name>\n",
+            escape(summary.display_name())
+        ));
+        content.push_str(&format!(
+            "    <type>{}</type>\n",
+            escape(&summary.activity_type)
+        ));
+        content.push_str("    <trkseg>\n");
+        for coordinate in coordinates {
+            content.push_str(&format!(
+                "      <trkpt lat=\"{}\" lon=\"{}\"/>\n",
+                coordinate.latitude, coordinate.longitude
+            ));
+        }
+        content.push_str("    </trkseg>\n  </trk>\n");
+    }
+
+    content.push_str("</gpx>\n");
+    content
+}
+
+fn write_kml(tracks: &[(TrackSummary, Vec<Coordinate>)]) -> String {
+    let mut content = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <kml xmlns=\"http://www.opengis.net/kml/2.2\">\n  <Document>\n",
+    );
+
+    for (summary, coordinates) in tracks {
+        content.push_str("    <Placemark>\n");
+        content.push_str(&format!(
+            "      <name>{}</name>\n",
+            escape(summary.display_name())
+        ));
+        content.push_str(&format!(
+            "      <description>{} {}</description>\n",
+            escape(&summary.activity_type),
+            escape(&summary.date)
+        ));
+        content.push_str(&format!(
+            "      <TimeStamp><when>{}</when></TimeStamp>\n",
+            escape(&summary.date)
+        ));
+        // KML uses the longitude first
+        let points: Vec<String> = coordinates
+            .iter()
+            .map(|coordinate| format!("{},{}", coordinate.longitude, coordinate.latitude))
+            .collect();
+        content.push_str(&format!(
+            "      <LineString><coordinates>{}</coordinates></LineString>\n",
+            points.join(" ")
+        ));
+        content.push_str("    </Placemark>\n");
+    }
+
+    content.push_str("  </Document>\n</kml>\n");
+    content
+}
+
+fn write_zip(tracks_directory: &Path, tracks: &[TrackSummary]) -> Result<Vec<u8>, Error> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+
+    for summary in tracks {
+        let path = resolve_path_inside(tracks_directory, &summary.filename)?;
+        zip.start_file(summary.filename.as_str(), options)?;
+        zip.write_all(&read_binary_file(&path)?)?;
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Build a single document with all the given tracks. GPX and KML use the simplified coordinates
+/// of the cache while the ZIP contains the original files.
+pub fn export_tracks(
+    tracks: Vec<TrackSummary>,
+    format: ExportFormat,
+    tracks_directory: &Path,
+    cache_directory: &Path,
+) -> Result<Vec<u8>, Error> {
+    if format == ExportFormat::Zip {
+        return write_zip(tracks_directory, &tracks);
+    }
+
+    let mut tracks_with_coordinates = Vec::new();
+    for summary in tracks {
+        let path = resolve_path_inside(cache_directory, &summary.filename)?;
+        let coordinates = parse_cached_coordinates(&read_file(&path)?);
+        tracks_with_coordinates.push((summary, coordinates));
+    }
+
+    let content = match format {
+        ExportFormat::Kml => write_kml(&tracks_with_coordinates),
+        _ => write_gpx(&tracks_with_coordinates),
+    };
+    Ok(content.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::model::{filter::Bounds, track::TrackMetrics};
+
+    fn create_track() -> (TrackSummary, Vec<Coordinate>) {
+        let summary = TrackSummary {
+            id: 1,
+            filename: "a&b.gpx".to_string(),
+            date: "2024-02-10T17:00:00+00:00".to_string(),
+            activity_type: "walking".to_string(),
+            bounds: Bounds {
+                north_west_latitude: 47.6,
+                north_west_longitude: 11.1,
+                south_east_latitude: 47.5,
+                south_east_longitude: 11.2,
+            },
+            metrics: TrackMetrics::default(),
+            name: None,
+            raw_activity_type: "walking".to_string(),
+            inferred_activity_type: None,
+            inferred_activity_type_confidence: None,
+            sport: None,
+            sub_sport: None,
+            sessions: Vec::new(),
+            untimed: false,
+        };
+        let coordinates = vec![Coordinate::new(47.5, 11.1), Coordinate::new(47.6, 11.2)];
+
+        (summary, coordinates)
+    }
+
+    #[test]
+    fn test_write_gpx() {
+        let gpx = write_gpx(&[create_track()]);
+
+        assert!(gpx.contains("<name>a&amp;b.gpx</name>"));
+        assert!(gpx.contains("<trkpt lat=\"47.5\" lon=\"11.1\"/>"));
+        assert!(gpx.contains("<trkpt lat=\"47.6\" lon=\"11.2\"/>"));
+    }
+
+    #[test]
+    fn test_write_kml() {
+        let kml = write_kml(&[create_track()]);
+
+        assert!(kml.contains("<coordinates>11.1,47.5 11.2,47.6</coordinates>"));
+    }
+}
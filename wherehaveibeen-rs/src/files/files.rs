@@ -3,19 +3,23 @@ use std::{
     path::Path,
 };
 
-use chrono::DateTime;
+use chrono::{DateTime, Utc};
 
 use crate::model::{
     coordinate::Coordinate,
     track::{TrackFile, TrackInformation},
+    trackpoint::TrackPoint,
 };
 
-use super::{fit::read_fit, gpx::read_gpx};
+use super::{
+    classifier::infer_activity_type, elevation::correct_elevations, fit::read_fit, gpx::read_gpx,
+    metrics::extract_track_metrics, tcx::read_tcx,
+};
 
-fn extract_track_coordinates(track_file: &TrackFile) -> Vec<Coordinate> {
+pub fn extract_track_coordinates(track_points: &[TrackPoint]) -> Vec<Coordinate> {
     let mut coordinates: Vec<Coordinate> = Vec::new();
 
-    for point in &track_file.track_points {
+    for point in track_points {
         let coordinate = Coordinate::new(point.latitude, point.longitude);
         coordinates.push(coordinate)
     }
@@ -23,18 +27,13 @@ fn extract_track_coordinates(track_file: &TrackFile) -> Vec<Coordinate> {
     coordinates
 }
 fn extract_track_information(track_file: &TrackFile) -> Result<TrackInformation, Error> {
-    let mut north_west_longitude: f32 = std::f32::NAN;
-    let mut north_west_latitude: f32 = std::f32::NAN;
-    let mut south_east_longitude: f32 = std::f32::NAN;
-    let mut south_east_latitude: f32 = std::f32::NAN;
-    let mut date: String = "".to_string();
-    let activity_type = track_file.activity_type.to_string();
+    let mut north_west_longitude: f64 = f64::NAN;
+    let mut north_west_latitude: f64 = f64::NAN;
+    let mut south_east_longitude: f64 = f64::NAN;
+    let mut south_east_latitude: f64 = f64::NAN;
+    let activity_type = track_file.activity_type.clone().unwrap_or_default();
 
     for coordinate in &track_file.track_points {
-        if date.eq("") {
-            date = coordinate.time.to_string();
-        }
-
         if north_west_latitude.is_nan() || coordinate.latitude > north_west_latitude {
             north_west_latitude = coordinate.latitude;
         }
@@ -64,39 +63,70 @@ fn extract_track_information(track_file: &TrackFile) -> Result<TrackInformation,
         ));
     }
 
-    // Check date format
-    if let Ok(value) = DateTime::parse_from_rfc3339(&date) {
-        date = value.to_rfc3339();
-    } else {
-        return Err(Error::new(ErrorKind::InvalidInput, "Invalid date format"));
-    }
-
-    Ok(TrackInformation::new(
+    // The time of the first point, or the date of the file when no point has time
+    let first_time = track_file
+        .track_points
+        .iter()
+        .map(|point| point.time.as_str())
+        .find(|time| DateTime::parse_from_rfc3339(time).is_ok());
+    let untimed = first_time.is_none();
+    let date = match first_time.or(track_file.date.as_deref()) {
+        Some(date) => DateTime::parse_from_rfc3339(date)
+            .map(|value| value.to_rfc3339())
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "Invalid date format"))?,
+        None => return Err(Error::new(ErrorKind::InvalidInput, "No date found")),
+    };
+
+    let mut track_information = TrackInformation::new(
         north_west_latitude,
         north_west_longitude,
         south_east_latitude,
         south_east_longitude,
         date,
         activity_type,
-    ))
+    );
+    track_information.metrics = extract_track_metrics(&track_file.track_points);
+    track_information.inferred_activity_type = infer_activity_type(&track_file.track_points);
+    track_information.sessions = track_file.sessions.clone();
+    track_information.untimed = untimed;
+
+    Ok(track_information)
 }
 
-pub fn get_track_information(file: &Path) -> Result<(TrackInformation, Vec<Coordinate>), Error> {
-    let track_file: TrackFile;
+/// Points and declared information of a GPX, FIT or TCX file as they are in the file
+pub fn read_track_file(file: &Path) -> Result<TrackFile, Error> {
     if file.extension().unwrap() == "gpx" {
-        track_file = read_gpx(file)?;
+        read_gpx(file)
     } else if file.extension().unwrap() == "fit" {
-        track_file = read_fit(file)?;
+        read_fit(file)
+    } else if file.extension().unwrap() == "tcx" {
+        read_tcx(file)
     } else {
         eprintln!("Invalid format {}", file.display());
-        return Err(Error::new(
+        Err(Error::new(
             std::io::ErrorKind::InvalidData,
             "Invalid format",
-        ));
+        ))
     }
+}
+
+fn get_modification_date(file: &Path) -> Option<String> {
+    let modified = std::fs::metadata(file)
+        .and_then(|metadata| metadata.modified())
+        .ok()?;
 
-    let coordinates = extract_track_coordinates(&track_file);
+    Some(DateTime::<Utc>::from(modified).to_rfc3339())
+}
+
+/// Information of the track to store and all its points
+pub fn get_track_information(file: &Path) -> Result<(TrackInformation, Vec<TrackPoint>), Error> {
+    let mut track_file = read_track_file(file)?;
+    if track_file.date.is_none() {
+        track_file.date = get_modification_date(file);
+    }
+    // Before the metrics so that the climbs are computed from the corrected elevations
+    correct_elevations(&mut track_file.track_points);
     let track_information = extract_track_information(&track_file)?;
 
-    Ok((track_information, coordinates))
+    Ok((track_information, track_file.track_points))
 }
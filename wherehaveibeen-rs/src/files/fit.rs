@@ -1,23 +1,32 @@
 use std::{
-    f32::NAN,
     fs::File,
     io::{Error, ErrorKind},
     path::Path,
 };
 
-use fitparser::{from_reader, profile::MesgNum, FitDataField, FitDataRecord, Value};
+use fitparser::{
+    from_reader,
+    profile::{
+        field_types::{Sport, SubSport},
+        MesgNum,
+    },
+    FitDataField, FitDataRecord, Value,
+};
 
 use crate::{
-    model::{track::TrackFile, trackpoint::TrackPoint},
+    model::{
+        track::{get_sessions_sport, TrackFile, TrackSession},
+        trackpoint::TrackPoint,
+    },
     utils::activity_type::sanitize_activity_type,
 };
 
-fn semicircles_to_degrees(semicircles: &i32) -> f32 {
-    const SEMICIRCLES_TO_DEGREES: f32 = 180.0 / (2u32.pow(31) as f32);
-    *semicircles as f32 * SEMICIRCLES_TO_DEGREES
+fn semicircles_to_degrees(semicircles: &i32) -> f64 {
+    const SEMICIRCLES_TO_DEGREES: f64 = 180.0 / (2u32.pow(31) as f64);
+    *semicircles as f64 * SEMICIRCLES_TO_DEGREES
 }
 
-fn get_coordinate_value(data_field: &FitDataField) -> Result<f32, Error> {
+fn get_coordinate_value(data_field: &FitDataField) -> Result<f64, Error> {
     if data_field.units() == "semicircles" {
         let value = data_field.value();
         match value {
@@ -31,12 +40,12 @@ fn get_coordinate_value(data_field: &FitDataField) -> Result<f32, Error> {
     Err(Error::new(ErrorKind::InvalidData, "Unexpected units"))
 }
 
-fn get_elevation_value(data_field: &FitDataField) -> Result<f32, Error> {
+fn get_elevation_value(data_field: &FitDataField) -> Result<f64, Error> {
     if data_field.units() == "m" {
         let value = data_field.value();
         match value {
             Value::Float64(val) => {
-                return Ok(*val as f32);
+                return Ok(*val);
             }
             _ => return Err(Error::new(ErrorKind::InvalidData, "Unexpected value type")),
         }
@@ -58,36 +67,87 @@ fn get_coordinate_timestamp(data_field: &FitDataField) -> Result<String, Error>
     Err(Error::new(ErrorKind::InvalidData, "Unexpected units"))
 }
 
-fn get_activity_type(data_field: &FitDataField) -> Result<String, Error> {
-    if data_field.units() == "" {
-        let value = data_field.value();
-        match value {
-            Value::String(val) => {
-                let activity_type = sanitize_activity_type(val);
-                return Ok(activity_type);
-            }
-            _ => return Err(Error::new(ErrorKind::InvalidData, "Unexpected value type")),
-        }
+// Sensor values are integers or already scaled to floats by the parser
+fn get_sensor_value(data_field: &FitDataField) -> Option<f64> {
+    match data_field.value() {
+        Value::UInt8(val) => Some(*val as f64),
+        Value::SInt8(val) => Some(*val as f64),
+        Value::UInt16(val) => Some(*val as f64),
+        Value::SInt16(val) => Some(*val as f64),
+        Value::UInt32(val) => Some(*val as f64),
+        Value::Float32(val) => Some(*val as f64),
+        Value::Float64(val) => Some(*val),
+        _ => None,
     }
-    Err(Error::new(ErrorKind::InvalidData, "Unexpected units"))
+}
+
+// Named values are decoded by the parser. Sports added to the FIT profile after this version
+// of the parser are numbers, which are kept as they are.
+fn get_enum_number(value: &Value) -> Option<Result<String, i64>> {
+    match value {
+        Value::String(val) => Some(Ok(val.clone())),
+        Value::Enum(val) | Value::UInt8(val) => Some(Err(*val as i64)),
+        Value::SInt64(val) => Some(Err(*val)),
+        _ => None,
+    }
+}
+
+fn get_sport(value: &Value) -> Option<String> {
+    match get_enum_number(value)? {
+        Ok(sport) => Some(sanitize_activity_type(&sport)),
+        Err(number) => match Sport::from(number) {
+            Sport::UnknownVariant(number) => Some(number.to_string()),
+            sport => Some(sport.to_string()),
+        },
+    }
+}
+
+fn get_sub_sport(value: &Value) -> Option<String> {
+    let sub_sport = match get_enum_number(value)? {
+        Ok(sub_sport) => sub_sport,
+        Err(number) => match SubSport::from(number) {
+            SubSport::UnknownVariant(number) => number.to_string(),
+            sub_sport => sub_sport.to_string(),
+        },
+    };
+
+    (sub_sport != SubSport::Generic.to_string()).then_some(sub_sport)
 }
 
 fn get_record_trackpoint(record: FitDataRecord) -> Result<TrackPoint, Error> {
-    let mut latitude: f32 = NAN;
-    let mut longitude: f32 = NAN;
-    let mut elevation: f32 = NAN;
+    let mut latitude: f64 = f64::NAN;
+    let mut longitude: f64 = f64::NAN;
+    let mut elevation: f64 = f64::NAN;
     let mut time: String = String::new();
+    let mut heart_rate: Option<f64> = None;
+    let mut cadence: Option<f64> = None;
+    let mut power: Option<f64> = None;
+    let mut speed: Option<f64> = None;
+    let mut enhanced_speed: Option<f64> = None;
+    let mut temperature: Option<f64> = None;
 
     for data_field in record.fields() {
         // println!("{:#?}", data_field);
         if data_field.name() == "position_lat" {
-            latitude = get_coordinate_value(&data_field)?;
+            latitude = get_coordinate_value(data_field)?;
         } else if data_field.name() == "position_long" {
-            longitude = get_coordinate_value(&data_field)?;
+            longitude = get_coordinate_value(data_field)?;
         } else if data_field.name() == "timestamp" {
-            time = get_coordinate_timestamp(&data_field)?;
+            time = get_coordinate_timestamp(data_field)?;
         } else if data_field.name() == "enhanced_altitude" {
-            elevation = get_elevation_value(&data_field)?;
+            elevation = get_elevation_value(data_field)?;
+        } else if data_field.name() == "heart_rate" {
+            heart_rate = get_sensor_value(data_field);
+        } else if data_field.name() == "cadence" {
+            cadence = get_sensor_value(data_field);
+        } else if data_field.name() == "power" {
+            power = get_sensor_value(data_field);
+        } else if data_field.name() == "speed" {
+            speed = get_sensor_value(data_field);
+        } else if data_field.name() == "enhanced_speed" {
+            enhanced_speed = get_sensor_value(data_field);
+        } else if data_field.name() == "temperature" {
+            temperature = get_sensor_value(data_field);
         }
     }
 
@@ -100,26 +160,43 @@ fn get_record_trackpoint(record: FitDataRecord) -> Result<TrackPoint, Error> {
         ));
     }
 
-    Ok(TrackPoint::new(latitude, longitude, elevation, time))
+    let mut track_point = TrackPoint::new(latitude, longitude, elevation, time);
+    track_point.heart_rate = heart_rate;
+    track_point.cadence = cadence;
+    track_point.power = power;
+    track_point.speed = enhanced_speed.or(speed);
+    track_point.temperature = temperature;
+
+    Ok(track_point)
 }
 
-fn get_session_activity_type(record: FitDataRecord) -> Result<String, Error> {
+// Session and sport messages have the same sport fields
+fn get_session(record: FitDataRecord) -> Option<TrackSession> {
+    let mut sport: Option<String> = None;
+    let mut sub_sport: Option<String> = None;
+    let mut start_time: Option<String> = None;
+
     for data_field in record.fields() {
-        // println!("{:#?}", data_field);
-        if data_field.name() == "sport" {
-            return Ok(get_activity_type(&data_field)?);
+        match data_field.name() {
+            "sport" => sport = get_sport(data_field.value()),
+            "sub_sport" => sub_sport = get_sub_sport(data_field.value()),
+            "start_time" => start_time = get_coordinate_timestamp(data_field).ok(),
+            _ => (),
         }
     }
 
-    return Err(Error::new(
-        ErrorKind::InvalidData,
-        "Activity type not found",
-    ));
+    Some(TrackSession {
+        sport: sport?,
+        sub_sport,
+        start_time,
+    })
 }
 
 fn get_track_file(data: Vec<FitDataRecord>) -> Result<TrackFile, Error> {
     let mut track_points: Vec<TrackPoint> = Vec::new();
-    let mut activity_type: String = "other".to_string();
+    let mut sessions: Vec<TrackSession> = Vec::new();
+    // Only used when there are no sessions
+    let mut sport_session: Option<TrackSession> = None;
     for record in data {
         if record.kind() == MesgNum::Record {
             let trackpoint = get_record_trackpoint(record);
@@ -130,30 +207,33 @@ fn get_track_file(data: Vec<FitDataRecord>) -> Result<TrackFile, Error> {
                 }
             }
         } else if record.kind() == MesgNum::Session {
-            let activity_type_result = get_session_activity_type(record);
-            match activity_type_result {
-                Err(_) => continue,
-                Ok(activity) => {
-                    activity_type = activity;
-                }
-            }
+            sessions.extend(get_session(record));
+        } else if record.kind() == MesgNum::Sport {
+            sport_session = get_session(record);
         }
     }
 
-    Ok(TrackFile::new(track_points, activity_type))
+    if sessions.is_empty() {
+        sessions.extend(sport_session);
+    }
+    let activity_type = get_sessions_sport(&sessions).map(|(sport, _)| sport.to_string());
+    let mut track_file = TrackFile::new(track_points, activity_type);
+    track_file.sessions = sessions;
+
+    Ok(track_file)
 }
 
 pub fn read_fit(path: &Path) -> Result<TrackFile, Error> {
     let mut fp = File::open(path)?;
     let data = from_reader(&mut fp);
     match data {
-        Ok(vector) => return Ok(get_track_file(vector)?),
+        Ok(vector) => get_track_file(vector),
         Err(err) => {
             eprintln!("{:#?}", err);
-            return Err(Error::new(
+            Err(Error::new(
                 ErrorKind::InvalidData,
                 "Activity type not found",
-            ));
+            ))
         }
     }
 }
@@ -162,10 +242,31 @@ pub fn read_fit(path: &Path) -> Result<TrackFile, Error> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_get_sport() {
+        assert_eq!(
+            get_sport(&Value::String("cycling".to_string())).unwrap(),
+            "cycling"
+        );
+        assert_eq!(get_sport(&Value::Enum(1)).unwrap(), "running");
+        assert_eq!(get_sport(&Value::SInt64(2)).unwrap(), "cycling");
+        assert_eq!(get_sport(&Value::SInt64(200)).unwrap(), "200");
+        assert_eq!(get_sport(&Value::Float64(1.0)), None);
+
+        assert_eq!(get_sub_sport(&Value::Enum(46)).unwrap(), "gravel_cycling");
+        assert_eq!(
+            get_sub_sport(&Value::String("trail".to_string())).unwrap(),
+            "trail"
+        );
+        assert_eq!(get_sub_sport(&Value::Enum(0)), None);
+    }
+
     #[test]
     fn test_read_fit_file() {
-        let file = Path::new("C:\\Users\\nck\\Development\\where-have-i-been\\wherehaveibeen-rs\\data\\track-fit\\1934901223.fit");
-        let result = read_fit(&file);
-        assert!(result.is_ok());
+        let file =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("data/fixtures/garmin-fenix-5-bike.fit");
+        let track_file = read_fit(&file).unwrap();
+        assert!(!track_file.track_points.is_empty());
+        assert_eq!(track_file.activity_type.as_deref(), Some("cycling"));
     }
 }
@@ -1,27 +1,67 @@
 use crate::{
-    model::{track::TrackFile, trackpoint::TrackPoint},
-    utils::{activity_type::sanitize_activity_type, file_utils::read_file},
+    model::{
+        coordinate::Coordinate,
+        layer::{FileLayers, Route, RouteSummary, Waypoint},
+        track::TrackFile,
+        trackpoint::TrackPoint,
+    },
+    utils::{
+        activity_type::sanitize_activity_type, file_utils::read_file, geo_utils::haversine_distance,
+    },
 };
 use quick_xml::de::from_str;
 use serde::{Deserialize, Serialize};
-use std::{io::Error, path::Path};
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+};
 
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
 struct Metadata {
     #[serde(rename = "name")]
     name: Option<String>,
+    time: Option<String>,
+}
+
+// Garmin TrackPointExtension. The namespace prefix of the elements is ignored.
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+struct TrackPointExtension {
+    #[serde(rename = "hr")]
+    heart_rate: Option<f64>,
+    #[serde(rename = "cad")]
+    cadence: Option<f64>,
+    speed: Option<f64>,
+    #[serde(rename = "atemp")]
+    temperature: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+struct PowerExtension {
+    #[serde(rename = "PowerInWatts")]
+    power: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+struct TrkPtExtensions {
+    #[serde(rename = "TrackPointExtension")]
+    track_point_extension: Option<TrackPointExtension>,
+    #[serde(rename = "PowerExtension")]
+    power_extension: Option<PowerExtension>,
+    // Written by Strava and others without a namespace
+    power: Option<f64>,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
 struct TrkPt {
     #[serde(rename = "@lat")]
-    latitude: Option<f32>,
+    latitude: Option<f64>,
     #[serde(rename = "@lon")]
-    longitude: Option<f32>,
+    longitude: Option<f64>,
     #[serde(rename = "ele")]
-    elevation: Option<f32>,
+    elevation: Option<f64>,
     #[serde(rename = "time")]
     time: Option<String>,
+    extensions: Option<TrkPtExtensions>,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
@@ -40,6 +80,34 @@ struct Track {
     segment: Option<TrkSeg>,
 }
 
+// Waypoints and the points of the routes have the same elements
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+struct Wpt {
+    #[serde(rename = "@lat")]
+    latitude: Option<f64>,
+    #[serde(rename = "@lon")]
+    longitude: Option<f64>,
+    #[serde(rename = "ele")]
+    elevation: Option<f64>,
+    time: Option<String>,
+    name: Option<String>,
+    #[serde(rename = "desc")]
+    description: Option<String>,
+    #[serde(rename = "sym")]
+    symbol: Option<String>,
+    #[serde(rename = "type")]
+    waypoint_type: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+struct Rte {
+    name: Option<String>,
+    #[serde(rename = "type")]
+    route_type: Option<String>,
+    #[serde(rename = "rtept")]
+    points: Option<Vec<Wpt>>,
+}
+
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
 struct Gpx {
     #[serde(rename = "@xmlns")]
@@ -54,18 +122,35 @@ struct Gpx {
     creator: Option<String>,
     #[serde(rename = "metadata")]
     metadata: Option<Metadata>,
+    #[serde(rename = "wpt")]
+    waypoints: Option<Vec<Wpt>>,
+    #[serde(rename = "rte")]
+    routes: Option<Vec<Rte>>,
     #[serde(rename = "trk")]
     track: Option<Track>,
 }
 
-fn get_activity_type(gpx: &Gpx) -> Result<String, Error> {
-    if let Some(ref track) = gpx.track {
-        if let Some(ref activity_type) = track.activity_type {
-            let activity_type = sanitize_activity_type(activity_type);
-            return Ok(activity_type);
-        }
+fn get_activity_type(gpx: &Gpx) -> Option<String> {
+    gpx.track
+        .as_ref()
+        .and_then(|track| track.activity_type.as_deref())
+        .filter(|activity_type| !activity_type.trim().is_empty())
+        .map(sanitize_activity_type)
+}
+
+fn add_sensor_values(track_point: &mut TrackPoint, extensions: &TrkPtExtensions) {
+    if let Some(extension) = &extensions.track_point_extension {
+        track_point.heart_rate = extension.heart_rate;
+        track_point.cadence = extension.cadence;
+        track_point.speed = extension.speed;
+        track_point.temperature = extension.temperature;
     }
-    Ok("other".to_string())
+    track_point.power = extensions.power.or_else(|| {
+        extensions
+            .power_extension
+            .as_ref()
+            .and_then(|extension| extension.power)
+    });
 }
 
 fn get_track_points(gpx: &Gpx) -> Result<Vec<TrackPoint>, Error> {
@@ -79,22 +164,22 @@ fn get_track_points(gpx: &Gpx) -> Result<Vec<TrackPoint>, Error> {
                     let elevation = point.elevation;
                     let time = point.time.clone();
 
-                    // If any point does not have the complete information the skip it
-                    if latitude.is_none()
-                        || longitude.is_none()
-                        || elevation.is_none()
-                        || time.is_none()
-                    {
-                        eprintln!("Point without complete information will be skipped");
+                    // Points without coordinates are skipped. The elevation can be taken from
+                    // the DEM tiles afterwards and planned tracks usually do not have time.
+                    if latitude.is_none() || longitude.is_none() {
+                        eprintln!("Point without coordinates will be skipped");
                         continue;
                     }
 
-                    let track_point = TrackPoint::new(
+                    let mut track_point = TrackPoint::new(
                         latitude.unwrap(),
                         longitude.unwrap(),
-                        elevation.unwrap(),
-                        time.unwrap(),
+                        elevation.unwrap_or(f64::NAN),
+                        time.unwrap_or_default(),
                     );
+                    if let Some(extensions) = &point.extensions {
+                        add_sensor_values(&mut track_point, extensions);
+                    }
                     track_points.push(track_point);
                 }
             }
@@ -103,12 +188,225 @@ fn get_track_points(gpx: &Gpx) -> Result<Vec<TrackPoint>, Error> {
     Ok(track_points)
 }
 
+fn parse_gpx(content: &str) -> Result<TrackFile, Error> {
+    let gpx = from_str::<Gpx>(content).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let activity_type = get_activity_type(&gpx);
+    let track_points = get_track_points(&gpx)?;
+
+    let mut track_file = TrackFile::new(track_points, activity_type);
+    track_file.date = gpx.metadata.and_then(|metadata| metadata.time);
+
+    Ok(track_file)
+}
+
 pub fn read_gpx(path: &Path) -> Result<TrackFile, Error> {
     let raw_file = read_file(path)?;
 
-    let gpx = from_str::<Gpx>(&raw_file).unwrap();
-    let activity_type = get_activity_type(&gpx)?;
-    let track_points = get_track_points(&gpx)?;
+    parse_gpx(&raw_file)
+}
+
+// Empty texts are written by some applications for the missing values
+fn get_text(text: &Option<String>) -> Option<String> {
+    text.as_deref()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+fn get_route(route: &Rte, filename: &str) -> Option<Route> {
+    let coordinates: Vec<Coordinate> = route
+        .points
+        .iter()
+        .flatten()
+        .filter_map(|point| Some(Coordinate::new(point.latitude?, point.longitude?)))
+        .collect();
+    if coordinates.len() < 2 {
+        return None;
+    }
+
+    let distance = coordinates
+        .windows(2)
+        .map(|pair| {
+            haversine_distance(
+                pair[0].latitude,
+                pair[0].longitude,
+                pair[1].latitude,
+                pair[1].longitude,
+            )
+        })
+        .sum();
+
+    Some(Route {
+        summary: RouteSummary {
+            id: 0,
+            filename: filename.to_string(),
+            file_id: None,
+            name: get_text(&route.name),
+            route_type: get_text(&route.route_type),
+            distance,
+        },
+        coordinates,
+    })
+}
+
+fn get_waypoint(waypoint: &Wpt, filename: &str) -> Option<Waypoint> {
+    Some(Waypoint {
+        id: 0,
+        filename: filename.to_string(),
+        file_id: None,
+        name: get_text(&waypoint.name),
+        description: get_text(&waypoint.description),
+        symbol: get_text(&waypoint.symbol),
+        waypoint_type: get_text(&waypoint.waypoint_type),
+        latitude: waypoint.latitude?,
+        longitude: waypoint.longitude?,
+        elevation: waypoint.elevation,
+        time: get_text(&waypoint.time),
+    })
+}
+
+fn parse_gpx_layers(content: &str, filename: &str) -> Result<FileLayers, Error> {
+    let gpx = from_str::<Gpx>(content).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+    Ok(FileLayers {
+        routes: gpx
+            .routes
+            .iter()
+            .flatten()
+            .filter_map(|route| get_route(route, filename))
+            .collect(),
+        waypoints: gpx
+            .waypoints
+            .iter()
+            .flatten()
+            .filter_map(|waypoint| get_waypoint(waypoint, filename))
+            .collect(),
+    })
+}
+
+/// Routes and waypoints of the file. They are stored apart from the track.
+pub fn read_gpx_layers(path: &Path) -> Result<FileLayers, Error> {
+    let raw_file = read_file(path)?;
+    let filename = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    parse_gpx_layers(&raw_file, &filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gpx_sensor_values() {
+        let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v1">
+  <trk>
+    <type>cycling</type>
+    <trkseg>
+      <trkpt lat="47.5" lon="11.1">
+        <ele>700.0</ele>
+        <time>2024-03-01T08:00:00Z</time>
+        <extensions>
+          <power>210</power>
+          <gpxtpx:TrackPointExtension>
+            <gpxtpx:atemp>18.5</gpxtpx:atemp>
+            <gpxtpx:hr>132</gpxtpx:hr>
+            <gpxtpx:cad>88</gpxtpx:cad>
+          </gpxtpx:TrackPointExtension>
+        </extensions>
+      </trkpt>
+      <trkpt lat="47.6" lon="11.2">
+        <ele>710.0</ele>
+        <time>2024-03-01T08:00:05Z</time>
+      </trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+        let track_file = parse_gpx(content).unwrap();
+        assert_eq!(track_file.activity_type.as_deref(), Some("cycling"));
+        assert_eq!(track_file.track_points.len(), 2);
+        let point = &track_file.track_points[0];
+        assert_eq!(point.heart_rate, Some(132.0));
+        assert_eq!(point.cadence, Some(88.0));
+        assert_eq!(point.power, Some(210.0));
+        assert_eq!(point.temperature, Some(18.5));
+        assert_eq!(point.speed, None);
+        assert_eq!(track_file.track_points[1].heart_rate, None);
+    }
+
+    #[test]
+    fn test_parse_gpx_without_elevation() {
+        let content = r#"<gpx version="1.1" creator="test">
+  <trk>
+    <trkseg>
+      <trkpt lat="47.5" lon="11.1"><time>2024-03-01T08:00:00Z</time></trkpt>
+      <trkpt lat="47.6" lon="11.2"><ele>710.0</ele><time>2024-03-01T08:00:05Z</time></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
 
-    Ok(TrackFile::new(track_points, activity_type))
+        let track_file = parse_gpx(content).unwrap();
+        assert_eq!(track_file.track_points.len(), 2);
+        assert!(track_file.track_points[0].elevation.is_nan());
+        assert_eq!(track_file.track_points[1].elevation, 710.0);
+    }
+
+    #[test]
+    fn test_parse_gpx_without_time() {
+        let content = r#"<gpx version="1.1" creator="test">
+  <metadata><name>Planned</name><time>2024-05-04T10:00:00Z</time></metadata>
+  <trk>
+    <trkseg>
+      <trkpt lat="47.5" lon="11.1"/>
+      <trkpt lat="47.6" lon="11.2"><ele>710.0</ele></trkpt>
+      <trkpt lon="11.2"/>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+        let track_file = parse_gpx(content).unwrap();
+        assert_eq!(track_file.track_points.len(), 2);
+        assert!(track_file.track_points[0].time.is_empty());
+        assert_eq!(track_file.date.as_deref(), Some("2024-05-04T10:00:00Z"));
+    }
+
+    #[test]
+    fn test_parse_gpx_layers() {
+        let content = r#"<gpx version="1.1" creator="test">
+  <wpt lat="47.42" lon="10.98">
+    <ele>2962</ele>
+    <name>Zugspitze</name>
+    <sym>Summit</sym>
+  </wpt>
+  <wpt lat="47.41" lon="11.01"><name>Knorrhütte</name><desc></desc></wpt>
+  <rte>
+    <name>Reintal</name>
+    <type>hiking</type>
+    <rtept lat="47.000" lon="11.0"/>
+    <rtept lat="47.001" lon="11.0"/>
+    <rtept lat="47.002" lon="11.0"/>
+  </rte>
+  <rte><rtept lat="47.0" lon="11.0"/></rte>
+</gpx>"#;
+
+        let layers = parse_gpx_layers(content, "zugspitze.gpx").unwrap();
+        assert_eq!(layers.waypoints.len(), 2);
+        assert_eq!(layers.waypoints[0].name.as_deref(), Some("Zugspitze"));
+        assert_eq!(layers.waypoints[0].symbol.as_deref(), Some("Summit"));
+        assert_eq!(layers.waypoints[0].elevation, Some(2962.0));
+        assert_eq!(layers.waypoints[1].description, None);
+        // Routes with a single point are left out
+        assert_eq!(layers.routes.len(), 1);
+        let route = &layers.routes[0].summary;
+        assert_eq!(route.name.as_deref(), Some("Reintal"));
+        assert_eq!(route.filename, "zugspitze.gpx");
+        assert!((route.distance - 222.4).abs() < 0.1);
+
+        // The track of the same file is still read
+        assert!(parse_gpx(content).unwrap().track_points.is_empty());
+    }
 }
@@ -1,6 +1,6 @@
 use std::{
     fs::{self, File},
-    io::{Error, ErrorKind, Read, Write},
+    io::{Error, Read, Write},
     path::{Path, PathBuf},
 };
 
@@ -9,7 +9,7 @@ use flate2::read::GzDecoder;
 fn get_destination_path(src: &Path) -> Option<PathBuf> {
     let folder = src.parent().unwrap();
     let filename = src.file_stem().unwrap();
-    return Some(folder.join(filename).to_path_buf());
+    Some(folder.join(filename).to_path_buf())
 }
 
 fn decompress_file(src_path: &Path, destination: PathBuf) -> Result<(), Error> {
@@ -48,10 +48,7 @@ pub fn decompress_gz(src_path: &Path) -> Result<(), Error> {
 
 pub fn decompress_all_gz_files(path: &Path) -> Result<(), Error> {
     if !path.is_dir() {
-        return Err(Error::new(
-            ErrorKind::Other,
-            "Provided path is not a directory.",
-        ));
+        return Err(Error::other("Provided path is not a directory."));
     }
 
     for entry in fs::read_dir(path)? {
@@ -73,13 +70,25 @@ mod tests {
 
     #[test]
     fn test_decompress_gz_file() {
-        let file = Path::new("C:\\Users\\nck\\Development\\where-have-i-been\\wherehaveibeen-rs\\data\\track-fit\\1934901223.fit.gz");
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/fixtures");
+        // The file is decompressed next to the source, so work on a copy
+        let folder = std::env::temp_dir().join(format!("whib-gz-test-{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let file = folder.join("garmin-fenix-5-bike.fit.gz");
+        fs::copy(fixtures.join("garmin-fenix-5-bike.fit.gz"), &file).unwrap();
+
         let result = decompress_gz(&file);
-        dbg!(&result);
+        let decompressed = fs::read(folder.join("garmin-fenix-5-bike.fit"));
+        fs::remove_dir_all(&folder).unwrap();
+
         assert!(result.is_ok());
+        assert_eq!(
+            decompressed.unwrap(),
+            fs::read(fixtures.join("garmin-fenix-5-bike.fit")).unwrap()
+        );
     }
-    #[test]
 
+    #[test]
     fn test_get_destination_path() {
         let file = Path::new("/Dev/track-fit/1934901223.fit.gz");
         let destination = get_destination_path(file).unwrap();
@@ -0,0 +1,523 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use crate::{
+    database::{
+        heatmap::HeatmapDatabase, layers::LayersDatabase, metadata::MetadataDatabase,
+        tracks::TracksDatabase,
+    },
+    model::{
+        coordinate::{Coordinate, StringifiedCoordinate},
+        filter::TrackFilter,
+        heatmap::HeatmapRebuild,
+        layer::{FileLayers, LayerImportResult},
+        track::{
+            ClassificationResult, ElevationCorrectionResult, IngestResult, RejectedFile,
+            TrackInformation, TrackSummary, UploadRejection, UploadResult,
+        },
+        trackpoint::TrackPoint,
+    },
+    utils::{
+        cache_utils::{parse_cached_coordinates, save_cached_track},
+        config::{get_cache_directory, get_config, get_tracks_directory},
+        file_access::resolve_path_inside,
+        file_utils::{
+            create_folder, get_content_hash, get_valid_gps_files, read_binary_file, read_file,
+        },
+    },
+};
+
+use super::{
+    elevation::correct_elevations,
+    files::{extract_track_coordinates, get_track_information, read_track_file},
+    gpx::read_gpx_layers,
+    gz::decompress_all_gz_files,
+    metrics::extract_track_metrics,
+    upload::UploadedFile,
+};
+
+pub fn add_coordinates_to_heatmap(
+    heatmap: &mut HashMap<StringifiedCoordinate, i32>,
+    coordinates: &Vec<Coordinate>,
+) {
+    // Round the coordinate to minimize points (5 decimals lose approx 1m of precision).
+    // Usually it would have 6 decimals.
+    let number_of_decimals = get_config().heatmap.decimals;
+    // First reduce the number of points that need to be inserted in the database by counting what
+    // is already in memory
+    for coordinate in coordinates {
+        let rounded_coordinate = StringifiedCoordinate::new(
+            format!("{:.1$}", coordinate.latitude, number_of_decimals),
+            format!("{:.1$}", coordinate.longitude, number_of_decimals),
+        );
+        *heatmap.entry(rounded_coordinate).or_insert(0) += 1;
+    }
+}
+
+/// Store a parsed track in the database and its points with their sensor values in the cache.
+/// The coordinates are added to the heatmap that must be saved afterwards.
+pub fn store_track(
+    tracks_db: &TracksDatabase,
+    cache_directory: &Path,
+    filename: &str,
+    track_information: TrackInformation,
+    track_points: &[TrackPoint],
+    file_hash: &str,
+    heatmap: &mut HashMap<StringifiedCoordinate, i32>,
+) -> Result<i64, Error> {
+    let id = tracks_db
+        .insert_new_file(filename, track_information, false, file_hash)
+        .map_err(Error::other)?;
+    save_cached_track(cache_directory, filename, track_points)?;
+    add_coordinates_to_heatmap(heatmap, &extract_track_coordinates(track_points));
+
+    Ok(id)
+}
+
+// Only GPX files have routes and waypoints
+fn read_file_layers(file_path: &Path) -> Result<FileLayers, Error> {
+    if file_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        != Some("gpx")
+    {
+        return Ok(FileLayers::default());
+    }
+
+    read_gpx_layers(file_path)
+}
+
+/// Store the routes and waypoints of a GPX file instead of the ones stored before. Other files do
+/// not have them.
+pub fn store_file_layers(
+    layers_db: &mut LayersDatabase,
+    file_path: &Path,
+    filename: &str,
+) -> Result<FileLayers, Error> {
+    let layers = read_file_layers(file_path)?;
+    layers_db
+        .replace_file_layers(filename, &layers)
+        .map_err(Error::other)?;
+
+    Ok(layers)
+}
+
+// The file is already in the tracks directory. Its cached points are removed if it can not be
+// stored in the database.
+fn store_uploaded_track(
+    tracks_db: &mut TracksDatabase,
+    file_path: &Path,
+    filename: &str,
+    file_hash: &str,
+) -> Result<i64, UploadRejection> {
+    let not_stored = |e: &dyn Display| UploadRejection::NotStored(e.to_string());
+
+    let (track_information, track_points) = get_track_information(file_path)
+        .map_err(|e| UploadRejection::Unparseable(e.to_string()))?;
+    let layers = read_file_layers(file_path).unwrap_or_else(|e| {
+        eprintln!("No routes or waypoints stored for {}: {}", filename, e);
+        FileLayers::default()
+    });
+    let mut heatmap: HashMap<StringifiedCoordinate, i32> = HashMap::new();
+    add_coordinates_to_heatmap(&mut heatmap, &extract_track_coordinates(&track_points));
+
+    let cache_directory = get_cache_directory();
+    save_cached_track(Path::new(&cache_directory), filename, &track_points)
+        .map_err(|e| not_stored(&e))?;
+    tracks_db
+        .store_track(filename, track_information, file_hash, &layers, &heatmap)
+        .map_err(|e| {
+            if let Err(cache_error) = remove_stored_file(&cache_directory, filename) {
+                eprintln!(
+                    "Can not remove the cached points of {}: {}",
+                    filename, cache_error
+                );
+            }
+            not_stored(&e)
+        })
+}
+
+// Write the file in the tracks directory and store it. Nothing is kept if it is not valid.
+fn ingest_uploaded_file(
+    tracks_db: &mut TracksDatabase,
+    file: &UploadedFile,
+) -> Result<i64, UploadRejection> {
+    let not_stored = |e: &dyn Display| UploadRejection::NotStored(e.to_string());
+
+    let file_hash = get_content_hash(&file.content);
+    if let Some(track) = tracks_db
+        .get_track_by_file_hash(&file_hash)
+        .map_err(|e| not_stored(&e))?
+    {
+        return Err(UploadRejection::Duplicate(track.id));
+    }
+
+    let tracks_directory = get_tracks_directory();
+    let file_path = Path::new(&tracks_directory).join(&file.filename);
+    if file_path.exists() {
+        return Err(UploadRejection::Exists);
+    }
+    std::fs::write(&file_path, &file.content).map_err(|e| not_stored(&e))?;
+
+    let track = store_uploaded_track(tracks_db, &file_path, &file.filename, &file_hash);
+    if track.is_err() {
+        std::fs::remove_file(&file_path).map_err(|e| not_stored(&e))?;
+    }
+
+    track
+}
+
+/// Store the uploaded files as if they had been found in the tracks directory on startup
+pub fn ingest_uploaded_files(files: Vec<UploadedFile>) -> Result<UploadResult, Error> {
+    let mut tracks_db = TracksDatabase::new().map_err(Error::other)?;
+
+    let mut result = UploadResult::default();
+    for file in files {
+        match ingest_uploaded_file(&mut tracks_db, &file) {
+            Ok(id) => {
+                let summary = tracks_db
+                    .get_track_summary(id)
+                    .map_err(Error::other)?
+                    .ok_or_else(|| Error::new(ErrorKind::NotFound, "The track was not stored"))?;
+                result.tracks.push(summary);
+            }
+            Err(reason) => {
+                eprintln!("Upload of {} rejected: {}", file.filename, reason);
+                result.rejected_files.push(RejectedFile {
+                    filename: file.filename,
+                    reason,
+                });
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+// Files that are already missing are not an error
+fn remove_stored_file(directory: &str, filename: &str) -> Result<(), Error> {
+    match resolve_path_inside(Path::new(directory), filename) {
+        Ok(path) => std::fs::remove_file(path),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Remove a track and its contribution to the heatmap. The original file is removed too,
+/// otherwise it would be stored again on the next startup.
+pub fn remove_track(tracks_db: &mut TracksDatabase, summary: &TrackSummary) -> Result<(), Error> {
+    let cache_directory = get_cache_directory();
+    let coordinates = match resolve_path_inside(Path::new(&cache_directory), &summary.filename)
+        .and_then(|path| read_file(&path))
+    {
+        Ok(content) => parse_cached_coordinates(&content),
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            eprintln!(
+                "No cached coordinates for {}. The heatmap is not updated",
+                summary.filename
+            );
+            Vec::new()
+        }
+        Err(e) => return Err(e),
+    };
+
+    let mut heatmap: HashMap<StringifiedCoordinate, i32> = HashMap::new();
+    add_coordinates_to_heatmap(&mut heatmap, &coordinates);
+    if !tracks_db
+        .delete_track(summary.id, &summary.filename, &heatmap)
+        .map_err(Error::other)?
+    {
+        return Err(Error::new(ErrorKind::NotFound, "The track was not found"));
+    }
+
+    // The files are only removed once the database no longer references them
+    remove_stored_file(&cache_directory, &summary.filename)?;
+    remove_stored_file(&get_tracks_directory(), &summary.filename)
+}
+
+/// Remove a file that has no track, like a GPX file with only routes and waypoints, together
+/// with its routes and waypoints. It has no cached coordinates nor points in the heatmap.
+pub fn remove_file_without_track(tracks_db: &mut TracksDatabase, id: i64) -> Result<(), Error> {
+    let not_found = || Error::new(ErrorKind::NotFound, "The file was not found");
+    let filename = tracks_db
+        .get_empty_track_filename(id)
+        .map_err(Error::other)?
+        .ok_or_else(not_found)?;
+    if !tracks_db
+        .delete_track(id, &filename, &HashMap::new())
+        .map_err(Error::other)?
+    {
+        return Err(not_found());
+    }
+
+    remove_stored_file(&get_tracks_directory(), &filename)
+}
+
+/// Heatmap of all the stored tracks computed from their cached coordinates
+pub fn compute_cached_heatmap(
+    tracks_db: &TracksDatabase,
+) -> Result<(HashMap<StringifiedCoordinate, i32>, HeatmapRebuild), Error> {
+    let tracks = tracks_db
+        .get_filtered_tracks(&TrackFilter::default())
+        .map_err(Error::other)?;
+
+    let cache_directory = get_cache_directory();
+    let mut heatmap: HashMap<StringifiedCoordinate, i32> = HashMap::new();
+    let mut result = HeatmapRebuild::default();
+    for track in tracks {
+        let content = resolve_path_inside(Path::new(&cache_directory), &track.filename)
+            .and_then(|path| read_file(&path));
+        match content {
+            Ok(content) => {
+                add_coordinates_to_heatmap(&mut heatmap, &parse_cached_coordinates(&content));
+                result.tracks += 1;
+            }
+            Err(e) => {
+                eprintln!("No cached coordinates for {}: {}", track.filename, e);
+                result.missing_tracks.push(track.filename);
+            }
+        }
+    }
+    result.points = heatmap.len();
+
+    Ok((heatmap, result))
+}
+
+/// Compute the heatmap again from the cached coordinates of all the tracks
+pub fn rebuild_heatmap() -> Result<HeatmapRebuild, Error> {
+    let tracks_db = TracksDatabase::new().map_err(Error::other)?;
+    let (mut heatmap, result) = compute_cached_heatmap(&tracks_db)?;
+
+    let mut heatmap_db = HeatmapDatabase::new().map_err(Error::other)?;
+    heatmap_db
+        .rebuild_heatmap(&mut heatmap, get_config().heatmap.decimals)
+        .map_err(Error::other)?;
+    let metadata_db = MetadataDatabase::new().map_err(Error::other)?;
+    metadata_db.increment_generation().map_err(Error::other)?;
+
+    Ok(result)
+}
+
+/// Rebuild the heatmap if its points are rounded to a different number of decimals than the
+/// configured one. Otherwise the new points would not match the stored ones.
+pub fn rebuild_heatmap_if_decimals_changed() -> Result<Option<HeatmapRebuild>, Error> {
+    let metadata_db = MetadataDatabase::new().map_err(Error::other)?;
+    let stored_decimals = metadata_db.get_heatmap_decimals().map_err(Error::other)?;
+    if stored_decimals == Some(get_config().heatmap.decimals) {
+        return Ok(None);
+    }
+
+    rebuild_heatmap().map(Some)
+}
+
+/// Guess the activity type of the tracks stored before it was done on ingestion. The original
+/// files are parsed again.
+pub fn classify_stored_tracks() -> Result<ClassificationResult, Error> {
+    let tracks_db = TracksDatabase::new().map_err(Error::other)?;
+    let tracks = tracks_db
+        .get_tracks_without_inferred_activity_type()
+        .map_err(Error::other)?;
+
+    let tracks_directory = get_tracks_directory();
+    let mut result = ClassificationResult::default();
+    for track in tracks {
+        let inference = resolve_path_inside(Path::new(&tracks_directory), &track.filename)
+            .and_then(|path| get_track_information(&path))
+            .map(|(track_information, _)| track_information.inferred_activity_type);
+        match inference {
+            Ok(Some(inference)) => {
+                tracks_db
+                    .set_inferred_activity_type(track.id, &inference)
+                    .map_err(Error::other)?;
+                result.classified_tracks += 1;
+            }
+            Ok(None) => result.unclassified_files.push(track.filename),
+            Err(e) => {
+                eprintln!("Can not classify {}: {}", track.filename, e);
+                result.unclassified_files.push(track.filename);
+            }
+        }
+    }
+
+    if result.classified_tracks > 0 {
+        let metadata_db = MetadataDatabase::new().map_err(Error::other)?;
+        metadata_db.increment_generation().map_err(Error::other)?;
+    }
+
+    Ok(result)
+}
+
+/// Take the elevations of the stored tracks from the DEM tiles and compute their climbs again.
+/// The original files are parsed again and the cached points are replaced.
+pub fn correct_stored_elevations() -> Result<ElevationCorrectionResult, Error> {
+    let tracks_db = TracksDatabase::new().map_err(Error::other)?;
+    let tracks = tracks_db
+        .get_filtered_tracks(&TrackFilter::default())
+        .map_err(Error::other)?;
+
+    let tracks_directory = get_tracks_directory();
+    let cache_directory = get_cache_directory();
+    let mut result = ElevationCorrectionResult::default();
+    for track in tracks {
+        let track_file = resolve_path_inside(Path::new(&tracks_directory), &track.filename)
+            .and_then(|path| read_track_file(&path));
+        let mut track_file = match track_file {
+            Ok(track_file) => track_file,
+            Err(e) => {
+                eprintln!("Can not correct {}: {}", track.filename, e);
+                result.failed_files.push(track.filename);
+                continue;
+            }
+        };
+
+        if correct_elevations(&mut track_file.track_points) == 0 {
+            result.unchanged_tracks += 1;
+            continue;
+        }
+        let metrics = extract_track_metrics(&track_file.track_points);
+        tracks_db
+            .update_elevation_metrics(track.id, &metrics)
+            .map_err(Error::other)?;
+        save_cached_track(
+            Path::new(&cache_directory),
+            &track.filename,
+            &track_file.track_points,
+        )?;
+        result.corrected_tracks += 1;
+    }
+
+    if result.corrected_tracks > 0 {
+        let metadata_db = MetadataDatabase::new().map_err(Error::other)?;
+        metadata_db.increment_generation().map_err(Error::other)?;
+    }
+
+    Ok(result)
+}
+
+/// Store the routes and waypoints of the GPX files stored before they were imported
+pub fn import_stored_layers() -> Result<LayerImportResult, Error> {
+    let tracks_db = TracksDatabase::new().map_err(Error::other)?;
+    let mut layers_db = LayersDatabase::new().map_err(Error::other)?;
+
+    let tracks_directory = get_tracks_directory();
+    let mut result = LayerImportResult::default();
+    for filename in tracks_db.get_all_filenames() {
+        let layers = resolve_path_inside(Path::new(&tracks_directory), &filename)
+            .and_then(|path| store_file_layers(&mut layers_db, &path, &filename));
+        match layers {
+            Ok(layers) => {
+                result.routes += layers.routes.len();
+                result.waypoints += layers.waypoints.len();
+            }
+            Err(e) => {
+                eprintln!("Can not import the layers of {}: {}", filename, e);
+                result.failed_files.push(filename);
+            }
+        }
+    }
+
+    // Clients must download again the routes and waypoints
+    let metadata_db = MetadataDatabase::new().map_err(Error::other)?;
+    metadata_db.increment_generation().map_err(Error::other)?;
+
+    Ok(result)
+}
+
+/// Store the files of the directory that have not been processed yet. Files that can not be
+/// parsed are registered as empty tracks so that they are not parsed again.
+pub fn ingest_directory(tracks_directory: &Path) -> Result<IngestResult, Error> {
+    let tracks_db = TracksDatabase::new().map_err(Error::other)?;
+    let mut heatmap_db = HeatmapDatabase::new().map_err(Error::other)?;
+    let mut layers_db = LayersDatabase::new().map_err(Error::other)?;
+
+    // Get what is already stored in the database to avoid processing again the same files
+    // that have already been processed
+    let processed_files = tracks_db.get_all_filenames();
+
+    // Create the folder where the simplified gpx tracks will be stored
+    let cache_directory = get_cache_directory();
+    let cache_path = Path::new(&cache_directory);
+    create_folder(cache_path)?;
+
+    // Sometimes the .fit tracks are stored as .fit.gz
+    decompress_all_gz_files(tracks_directory)?;
+
+    let files = get_valid_gps_files(tracks_directory)?;
+    let mut heatmap: HashMap<StringifiedCoordinate, i32> = HashMap::new();
+    let mut result = IngestResult::default();
+    for filename in files {
+        // Do not reprocess data already stored in the database for performance
+        // on the second startup
+        if processed_files.contains(&filename) {
+            continue;
+        }
+        let file_path = tracks_directory.join(&filename);
+        // Used to detect uploads of files that are already stored
+        let file_hash = read_binary_file(&file_path)
+            .map(|content| get_content_hash(&content))
+            .unwrap_or_default();
+
+        match get_track_information(file_path.as_path()) {
+            Ok((track_information, track_points)) => {
+                store_track(
+                    &tracks_db,
+                    cache_path,
+                    &filename,
+                    track_information,
+                    &track_points,
+                    &file_hash,
+                    &mut heatmap,
+                )?;
+                result.new_tracks += 1;
+            }
+            Err(e) => {
+                eprintln!("No track information found for {}", filename);
+                eprintln!("Error: {}", e);
+                // The file is still inserted to prevent duplicated analysis on the next restart
+                tracks_db
+                    .insert_new_file(
+                        &filename,
+                        TrackInformation::create_empty_track(),
+                        true,
+                        &file_hash,
+                    )
+                    .map_err(Error::other)?;
+                result.invalid_files += 1;
+            }
+        }
+
+        // Files with only routes or waypoints are stored as empty tracks
+        match store_file_layers(&mut layers_db, &file_path, &filename) {
+            Ok(layers) => {
+                result.new_routes += layers.routes.len();
+                result.new_waypoints += layers.waypoints.len();
+            }
+            Err(e) => eprintln!("No routes or waypoints stored for {}: {}", filename, e),
+        }
+    }
+
+    println!("Saving heatmap...");
+    heatmap_db
+        .update_heatmap(&mut heatmap)
+        .map_err(Error::other)?;
+
+    if result.new_tracks > 0 || result.new_routes > 0 || result.new_waypoints > 0 {
+        // Clients must download again the heatmap and the layers
+        let metadata_db = MetadataDatabase::new().map_err(Error::other)?;
+        metadata_db.increment_generation().map_err(Error::other)?;
+    }
+
+    println!("Creating indices...");
+    heatmap_db.create_table_indices().map_err(Error::other)?;
+    tracks_db.create_table_indices().map_err(Error::other)?;
+
+    // Release connection
+    tracks_db.conn.close().map_err(|(_, e)| Error::other(e))?;
+    heatmap_db.conn.close().map_err(|(_, e)| Error::other(e))?;
+
+    Ok(result)
+}
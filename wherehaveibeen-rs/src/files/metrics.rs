@@ -0,0 +1,142 @@
+use chrono::DateTime;
+
+use crate::{
+    model::{track::TrackMetrics, trackpoint::TrackPoint},
+    utils::geo_utils::haversine_distance,
+};
+
+// Below this speed (m/s) the time between two points is not counted as moving time
+pub const MOVING_SPEED_THRESHOLD: f64 = 0.5;
+// Altitude changes smaller than this (m) are considered noise for the gain and loss
+const ELEVATION_THRESHOLD: f64 = 2.0;
+
+pub fn get_timestamp(point: &TrackPoint) -> Option<i64> {
+    DateTime::parse_from_rfc3339(&point.time)
+        .ok()
+        .map(|time| time.timestamp_millis())
+}
+
+fn add_elevation_metrics(metrics: &mut TrackMetrics, track_points: &[TrackPoint]) {
+    let mut elevations = track_points
+        .iter()
+        .map(|point| point.elevation)
+        .filter(|elevation| !elevation.is_nan());
+
+    let Some(first_elevation) = elevations.next() else {
+        return;
+    };
+
+    let mut gain = 0.0;
+    let mut loss = 0.0;
+    let mut min_altitude = first_elevation;
+    let mut max_altitude = first_elevation;
+    let mut reference_elevation = first_elevation;
+    for elevation in elevations {
+        min_altitude = min_altitude.min(elevation);
+        max_altitude = max_altitude.max(elevation);
+
+        let difference = elevation - reference_elevation;
+        if difference.abs() >= ELEVATION_THRESHOLD {
+            if difference > 0.0 {
+                gain += difference;
+            } else {
+                loss -= difference;
+            }
+            reference_elevation = elevation;
+        }
+    }
+
+    metrics.elevation_gain = Some(gain);
+    metrics.elevation_loss = Some(loss);
+    metrics.min_altitude = Some(min_altitude);
+    metrics.max_altitude = Some(max_altitude);
+}
+
+pub fn extract_track_metrics(track_points: &[TrackPoint]) -> TrackMetrics {
+    let mut metrics = TrackMetrics::default();
+
+    for (previous, current) in track_points.iter().zip(track_points.iter().skip(1)) {
+        let distance = haversine_distance(
+            previous.latitude,
+            previous.longitude,
+            current.latitude,
+            current.longitude,
+        );
+        metrics.distance += distance;
+
+        if let (Some(start), Some(end)) = (get_timestamp(previous), get_timestamp(current)) {
+            let seconds = (end - start) as f64 / 1000.0;
+            if seconds <= 0.0 {
+                continue;
+            }
+
+            let speed = distance / seconds;
+            if speed >= MOVING_SPEED_THRESHOLD {
+                metrics.moving_time += seconds;
+                metrics.max_speed = metrics.max_speed.max(speed);
+            }
+        }
+    }
+
+    let first_timestamp = track_points.iter().find_map(get_timestamp);
+    let last_timestamp = track_points.iter().rev().find_map(get_timestamp);
+    if let (Some(start), Some(end)) = (first_timestamp, last_timestamp) {
+        metrics.elapsed_time = (end - start).max(0) as f64 / 1000.0;
+    }
+
+    if metrics.moving_time > 0.0 {
+        metrics.average_speed = metrics.distance / metrics.moving_time;
+    }
+
+    add_elevation_metrics(&mut metrics, track_points);
+
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_point(latitude: f64, elevation: f64, time: &str) -> TrackPoint {
+        TrackPoint::new(latitude, 11.0, elevation, time.to_string())
+    }
+
+    #[test]
+    fn test_extract_track_metrics() {
+        // Every 0.001 degrees of latitude are around 111 meters
+        let track_points = vec![
+            create_point(48.000, 520.0, "2023-05-01T07:00:00Z"),
+            create_point(48.001, 525.0, "2023-05-01T07:00:30Z"),
+            create_point(48.002, 522.0, "2023-05-01T07:01:00Z"),
+            // Stop for 10 minutes
+            create_point(48.002, 522.0, "2023-05-01T07:11:00Z"),
+            create_point(48.003, 530.0, "2023-05-01T07:11:30Z"),
+            create_point(48.003, 531.0, "2023-05-01T07:12:00Z"),
+        ];
+
+        let metrics = extract_track_metrics(&track_points);
+
+        assert!((metrics.distance - 333.6).abs() < 1.0);
+        assert_eq!(metrics.elapsed_time, 720.0);
+        assert_eq!(metrics.moving_time, 90.0);
+        assert!((metrics.average_speed - 3.71).abs() < 0.01);
+        assert!((metrics.max_speed - 3.71).abs() < 0.01);
+        assert_eq!(metrics.elevation_gain, Some(13.0));
+        assert_eq!(metrics.elevation_loss, Some(3.0));
+        assert_eq!(metrics.min_altitude, Some(520.0));
+        assert_eq!(metrics.max_altitude, Some(531.0));
+    }
+
+    #[test]
+    fn test_extract_track_metrics_without_elevation() {
+        let track_points = vec![
+            create_point(48.000, f64::NAN, "2023-05-01T07:00:00Z"),
+            create_point(48.001, f64::NAN, "2023-05-01T07:00:30Z"),
+        ];
+
+        let metrics = extract_track_metrics(&track_points);
+
+        assert_eq!(metrics.elevation_gain, None);
+        assert_eq!(metrics.min_altitude, None);
+    }
+}
@@ -1,4 +1,15 @@
 pub mod gpx;
+#[allow(clippy::module_inception)]
 pub mod files;
+pub mod export;
 pub mod fit;
-pub mod gz;
\ No newline at end of file
+pub mod gz;
+pub mod ingest;
+pub mod metrics;
+pub mod tcx;
+pub mod upload;
+pub mod verify;
+pub mod classifier;
+pub mod streams;
+pub mod profile;
+pub mod elevation;
\ No newline at end of file
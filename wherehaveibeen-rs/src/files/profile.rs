@@ -0,0 +1,144 @@
+use crate::model::{
+    stream::{ProfileSample, TrackProfile},
+    trackpoint::TrackPoint,
+};
+
+use super::streams::extract_track_streams;
+
+#[derive(Debug, Clone, Copy)]
+struct ProfilePoint {
+    distance: f64,
+    elevation: Option<f64>,
+    time: Option<f64>,
+}
+
+// Missing values are taken from the other end of the segment
+fn interpolate(start: Option<f64>, end: Option<f64>, fraction: f64) -> Option<f64> {
+    match (start, end) {
+        (Some(start), Some(end)) => Some(start + (end - start) * fraction),
+        (value, None) | (None, value) => value,
+    }
+}
+
+fn get_profile_points(track_points: &[TrackPoint]) -> Vec<ProfilePoint> {
+    let streams = extract_track_streams(track_points);
+    let get_value = |channel: &Option<Vec<Option<f64>>>, index: usize| {
+        channel.as_ref().and_then(|values| values[index])
+    };
+
+    streams
+        .distance
+        .iter()
+        .enumerate()
+        .map(|(index, distance)| ProfilePoint {
+            distance: *distance,
+            elevation: get_value(&streams.elevation, index),
+            time: get_value(&streams.time, index),
+        })
+        .collect()
+}
+
+// Points at equal distances along the track, interpolated between the original points
+fn downsample(points: Vec<ProfilePoint>, count: usize) -> Vec<ProfilePoint> {
+    if points.len() <= count {
+        return points;
+    }
+
+    let total_distance = points[points.len() - 1].distance;
+    let mut index = 0;
+    (0..count)
+        .map(|sample| {
+            let distance = total_distance * sample as f64 / (count - 1) as f64;
+            while index + 2 < points.len() && points[index + 1].distance < distance {
+                index += 1;
+            }
+
+            let start = points[index];
+            let end = points[index + 1];
+            let length = end.distance - start.distance;
+            let fraction = if length > 0.0 {
+                ((distance - start.distance) / length).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+
+            ProfilePoint {
+                distance,
+                elevation: interpolate(start.elevation, end.elevation, fraction),
+                time: interpolate(start.time, end.time, fraction),
+            }
+        })
+        .collect()
+}
+
+fn get_sample(point: &ProfilePoint, start: &ProfilePoint, end: &ProfilePoint) -> ProfileSample {
+    let distance = end.distance - start.distance;
+    let speed = match (start.time, end.time) {
+        (Some(start), Some(end)) if end > start => Some(distance / (end - start)),
+        _ => None,
+    };
+    let grade = match (start.elevation, end.elevation) {
+        (Some(start), Some(end)) if distance > 0.0 => Some((end - start) / distance * 100.0),
+        _ => None,
+    };
+
+    ProfileSample {
+        distance: point.distance,
+        elevation: point.elevation,
+        speed,
+        grade,
+        time: point.time,
+    }
+}
+
+/// Profile of the track with at most `count` samples
+pub fn extract_track_profile(track_points: &[TrackPoint], count: usize) -> TrackProfile {
+    let points = downsample(get_profile_points(track_points), count);
+
+    let samples = (0..points.len())
+        .map(|index| {
+            let end = index.max(1).min(points.len() - 1);
+            let start = end.saturating_sub(1);
+            get_sample(&points[index], &points[start], &points[end])
+        })
+        .collect();
+
+    TrackProfile { samples }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_point(latitude: f64, elevation: f64, time: &str) -> TrackPoint {
+        TrackPoint::new(latitude, 11.0, elevation, time.to_string())
+    }
+
+    #[test]
+    fn test_extract_track_profile() {
+        // Every 0.001 degrees of latitude are around 111 meters
+        let track_points = vec![
+            create_point(48.000, 500.0, "2023-05-01T07:00:00Z"),
+            create_point(48.001, 510.0, "2023-05-01T07:00:30Z"),
+            create_point(48.002, 520.0, "2023-05-01T07:01:00Z"),
+            create_point(48.003, f64::NAN, "2023-05-01T07:01:30Z"),
+            create_point(48.004, 520.0, "2023-05-01T07:02:00Z"),
+        ];
+
+        let profile = extract_track_profile(&track_points, 3);
+        let samples = &profile.samples;
+
+        assert_eq!(samples.len(), 3);
+        assert_eq!(samples[0].distance, 0.0);
+        assert!((samples[1].distance - 222.4).abs() < 0.1);
+        assert!((samples[1].elevation.unwrap() - 520.0).abs() < 0.01);
+        assert_eq!(samples[2].time, Some(120.0));
+        assert!((samples[0].grade.unwrap() - 9.0).abs() < 0.1);
+        assert!(samples[2].grade.unwrap().abs() < 0.01);
+        assert!((samples[2].speed.unwrap() - 3.71).abs() < 0.01);
+
+        let profile = extract_track_profile(&track_points, 500);
+        assert_eq!(profile.samples.len(), 5);
+        assert_eq!(profile.samples[3].elevation, None);
+    }
+}
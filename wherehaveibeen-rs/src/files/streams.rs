@@ -0,0 +1,69 @@
+use crate::{
+    model::{
+        stream::{Channel, TrackStreams},
+        trackpoint::TrackPoint,
+    },
+    utils::geo_utils::haversine_distance,
+};
+
+use super::metrics::get_timestamp;
+
+fn get_channel(track_points: &[TrackPoint], value: impl Fn(&TrackPoint) -> Option<f64>) -> Channel {
+    let values: Vec<Option<f64>> = track_points.iter().map(value).collect();
+
+    values.iter().any(Option::is_some).then_some(values)
+}
+
+pub fn extract_track_streams(track_points: &[TrackPoint]) -> TrackStreams {
+    let mut distance = 0.0;
+    let mut distances: Vec<f64> = Vec::new();
+    for (index, point) in track_points.iter().enumerate() {
+        if index > 0 {
+            let previous = &track_points[index - 1];
+            distance += haversine_distance(
+                previous.latitude,
+                previous.longitude,
+                point.latitude,
+                point.longitude,
+            );
+        }
+        distances.push(distance);
+    }
+
+    let start = track_points.iter().find_map(get_timestamp);
+    TrackStreams {
+        distance: distances,
+        time: get_channel(track_points, |point| {
+            Some((get_timestamp(point)? - start?) as f64 / 1000.0)
+        }),
+        elevation: get_channel(track_points, |point| {
+            (!point.elevation.is_nan()).then_some(point.elevation)
+        }),
+        heart_rate: get_channel(track_points, |point| point.heart_rate),
+        cadence: get_channel(track_points, |point| point.cadence),
+        power: get_channel(track_points, |point| point.power),
+        speed: get_channel(track_points, |point| point.speed),
+        temperature: get_channel(track_points, |point| point.temperature),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_track_streams() {
+        let mut first = TrackPoint::new(48.000, 11.0, 520.0, "2023-05-01T07:00:00Z".to_string());
+        first.heart_rate = Some(120.0);
+        let second = TrackPoint::new(48.001, 11.0, f64::NAN, "2023-05-01T07:00:30Z".to_string());
+
+        let streams = extract_track_streams(&[first, second]);
+
+        assert_eq!(streams.distance[0], 0.0);
+        assert!((streams.distance[1] - 111.2).abs() < 0.1);
+        assert_eq!(streams.time, Some(vec![Some(0.0), Some(30.0)]));
+        assert_eq!(streams.elevation, Some(vec![Some(520.0), None]));
+        assert_eq!(streams.heart_rate, Some(vec![Some(120.0), None]));
+        assert_eq!(streams.power, None);
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{
+    model::{track::TrackFile, trackpoint::TrackPoint},
+    utils::{activity_type::sanitize_activity_type, file_utils::read_file},
+};
+use quick_xml::de::from_str;
+use serde::Deserialize;
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct Position {
+    #[serde(rename = "LatitudeDegrees")]
+    latitude: f64,
+    #[serde(rename = "LongitudeDegrees")]
+    longitude: f64,
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct HeartRate {
+    #[serde(rename = "Value")]
+    value: f64,
+}
+
+// Garmin ActivityExtension
+#[derive(Deserialize, PartialEq, Debug)]
+struct TrackpointExtension {
+    #[serde(rename = "Speed")]
+    speed: Option<f64>,
+    #[serde(rename = "Watts")]
+    power: Option<f64>,
+    #[serde(rename = "RunCadence")]
+    run_cadence: Option<f64>,
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct TrackpointExtensions {
+    #[serde(rename = "TPX")]
+    tpx: Option<TrackpointExtension>,
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct Trackpoint {
+    #[serde(rename = "Time")]
+    time: Option<String>,
+    #[serde(rename = "Position")]
+    position: Option<Position>,
+    #[serde(rename = "AltitudeMeters")]
+    altitude: Option<f64>,
+    #[serde(rename = "HeartRateBpm")]
+    heart_rate: Option<HeartRate>,
+    #[serde(rename = "Cadence")]
+    cadence: Option<f64>,
+    #[serde(rename = "Extensions")]
+    extensions: Option<TrackpointExtensions>,
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct Track {
+    #[serde(rename = "Trackpoint", default)]
+    points: Vec<Trackpoint>,
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct Lap {
+    #[serde(rename = "Track", default)]
+    tracks: Vec<Track>,
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct Activity {
+    #[serde(rename = "@Sport")]
+    sport: Option<String>,
+    #[serde(rename = "Lap", default)]
+    laps: Vec<Lap>,
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct Activities {
+    #[serde(rename = "Activity", default)]
+    activities: Vec<Activity>,
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct TrainingCenterDatabase {
+    #[serde(rename = "Activities")]
+    activities: Option<Activities>,
+}
+
+fn get_track_points(activity: &Activity) -> Vec<TrackPoint> {
+    let mut track_points: Vec<TrackPoint> = Vec::new();
+
+    let points = activity
+        .laps
+        .iter()
+        .flat_map(|lap| &lap.tracks)
+        .flat_map(|track| &track.points);
+    for point in points {
+        // Points recorded without GPS signal (e.g. only heart rate) are skipped
+        if let (Some(position), Some(time)) = (&point.position, &point.time) {
+            let mut track_point = TrackPoint::new(
+                position.latitude,
+                position.longitude,
+                point.altitude.unwrap_or(f64::NAN),
+                time.clone(),
+            );
+            let extension = point
+                .extensions
+                .as_ref()
+                .and_then(|extensions| extensions.tpx.as_ref());
+            track_point.heart_rate = point.heart_rate.as_ref().map(|heart_rate| heart_rate.value);
+            track_point.cadence = point
+                .cadence
+                .or_else(|| extension.and_then(|extension| extension.run_cadence));
+            track_point.power = extension.and_then(|extension| extension.power);
+            track_point.speed = extension.and_then(|extension| extension.speed);
+            track_points.push(track_point);
+        }
+    }
+
+    track_points
+}
+
+fn parse_tcx(content: &str) -> Result<TrackFile, Error> {
+    let tcx = from_str::<TrainingCenterDatabase>(content)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+    // Only the first activity is used as each file is stored as a single track
+    let activity = tcx
+        .activities
+        .and_then(|activities| activities.activities.into_iter().next())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "No activity found in the file"))?;
+    let activity_type = activity
+        .sport
+        .as_deref()
+        .filter(|sport| !sport.trim().is_empty())
+        .map(sanitize_activity_type);
+
+    Ok(TrackFile::new(get_track_points(&activity), activity_type))
+}
+
+pub fn read_tcx(path: &Path) -> Result<TrackFile, Error> {
+    let raw_file = read_file(path)?;
+
+    parse_tcx(&raw_file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tcx() {
+        let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+  <Activities>
+    <Activity Sport="Running">
+      <Id>2024-03-01T08:00:00Z</Id>
+      <Lap StartTime="2024-03-01T08:00:00Z">
+        <Track>
+          <Trackpoint>
+            <Time>2024-03-01T08:00:00Z</Time>
+            <Position>
+              <LatitudeDegrees>47.5</LatitudeDegrees>
+              <LongitudeDegrees>11.1</LongitudeDegrees>
+            </Position>
+            <AltitudeMeters>700.0</AltitudeMeters>
+            <HeartRateBpm><Value>110</Value></HeartRateBpm>
+            <Extensions>
+              <ns3:TPX xmlns:ns3="http://www.garmin.com/xmlschemas/ActivityExtension/v2">
+                <ns3:Speed>2.9</ns3:Speed>
+                <ns3:RunCadence>82</ns3:RunCadence>
+              </ns3:TPX>
+            </Extensions>
+          </Trackpoint>
+          <Trackpoint>
+            <Time>2024-03-01T08:00:05Z</Time>
+            <HeartRateBpm><Value>120</Value></HeartRateBpm>
+          </Trackpoint>
+        </Track>
+      </Lap>
+      <Lap StartTime="2024-03-01T08:05:00Z">
+        <Track>
+          <Trackpoint>
+            <Time>2024-03-01T08:05:00Z</Time>
+            <Position>
+              <LatitudeDegrees>47.6</LatitudeDegrees>
+              <LongitudeDegrees>11.2</LongitudeDegrees>
+            </Position>
+          </Trackpoint>
+        </Track>
+      </Lap>
+    </Activity>
+  </Activities>
+</TrainingCenterDatabase>"#;
+
+        let track_file = parse_tcx(content).unwrap();
+        assert_eq!(track_file.activity_type.as_deref(), Some("Running"));
+        assert_eq!(track_file.track_points.len(), 2);
+        assert_eq!(track_file.track_points[0].elevation, 700.0);
+        assert!(track_file.track_points[1].elevation.is_nan());
+        assert_eq!(track_file.track_points[0].heart_rate, Some(110.0));
+        assert_eq!(track_file.track_points[0].cadence, Some(82.0));
+        assert_eq!(track_file.track_points[0].speed, Some(2.9));
+        assert_eq!(track_file.track_points[1].cadence, None);
+        assert_eq!(track_file.track_points[1].latitude, 47.6);
+    }
+
+    #[test]
+    fn test_parse_invalid_tcx() {
+        assert!(parse_tcx("<gpx></gpx>").is_err());
+        assert!(parse_tcx("not xml").is_err());
+    }
+}
@@ -0,0 +1,222 @@
+use std::{
+    io::{Cursor, Error, ErrorKind, Read},
+    path::Path,
+};
+
+use flate2::read::GzDecoder;
+use zip::ZipArchive;
+
+use crate::utils::file_utils::is_supported_track_file;
+
+/// Maximum size of the body of an upload request
+pub const MAX_UPLOAD_SIZE: usize = 50 * 1024 * 1024;
+// Compressed files are limited after decompression as well to protect against compression bombs.
+// The limit is shared by all the files of a request, including the ones nested in an archive.
+pub const MAX_DECOMPRESSED_SIZE: u64 = 200 * 1024 * 1024;
+const MAX_ARCHIVE_ENTRIES: usize = 10_000;
+
+#[derive(Debug)]
+pub struct UploadedFile {
+    pub filename: String,
+    pub content: Vec<u8>,
+}
+
+// Only the name of the file is kept so that it can not be written outside of the tracks directory
+fn sanitize_filename(filename: &str) -> Option<String> {
+    let name = Path::new(filename).file_name()?.to_str()?;
+    if name.starts_with('.') {
+        return None;
+    }
+
+    Some(name.to_string())
+}
+
+// The size of the content is subtracted from the remaining size
+fn read_limited(reader: impl Read, remaining_size: &mut u64) -> Result<Vec<u8>, Error> {
+    let mut content = Vec::new();
+    reader.take(*remaining_size + 1).read_to_end(&mut content)?;
+    if content.len() as u64 > *remaining_size {
+        return Err(Error::new(
+            ErrorKind::FileTooLarge,
+            "The decompressed files are too large",
+        ));
+    }
+
+    *remaining_size -= content.len() as u64;
+    Ok(content)
+}
+
+fn unsupported_file(filename: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("{} is not a GPX, FIT or TCX file", filename),
+    )
+}
+
+// A track file that can be compressed with gzip
+fn extract_file(
+    filename: &str,
+    content: Vec<u8>,
+    remaining_size: &mut u64,
+) -> Result<UploadedFile, Error> {
+    let filename = sanitize_filename(filename).ok_or_else(|| unsupported_file(filename))?;
+
+    let (filename, content) = match filename.strip_suffix(".gz") {
+        Some(name) => (
+            name.to_string(),
+            read_limited(GzDecoder::new(&content[..]), remaining_size)?,
+        ),
+        None => (filename, content),
+    };
+    if !is_supported_track_file(&filename) {
+        return Err(unsupported_file(&filename));
+    }
+
+    Ok(UploadedFile { filename, content })
+}
+
+fn extract_zip(
+    content: Vec<u8>,
+    max_entries: usize,
+    remaining_size: &mut u64,
+) -> Result<Vec<UploadedFile>, Error> {
+    let mut archive = ZipArchive::new(Cursor::new(content))?;
+    if archive.len() > max_entries {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("The archive has more than {} entries", max_entries),
+        ));
+    }
+
+    let mut files = Vec::new();
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let entry_content = read_limited(entry, remaining_size)?;
+        // Other files of the archive (e.g. pictures) are ignored
+        match extract_file(&name, entry_content, remaining_size) {
+            Ok(file) => files.push(file),
+            Err(e) if e.kind() == ErrorKind::FileTooLarge => return Err(e),
+            Err(_) => {}
+        }
+    }
+
+    Ok(files)
+}
+
+/// Get the track files of an uploaded file. It can be a GPX, FIT or TCX file, optionally
+/// compressed with gzip, or a ZIP archive containing them.
+/// The decompressed size is subtracted from `remaining_size`, which is shared by the whole request.
+pub fn extract_track_files(
+    filename: &str,
+    content: Vec<u8>,
+    remaining_size: &mut u64,
+) -> Result<Vec<UploadedFile>, Error> {
+    if filename.ends_with(".zip") {
+        let files = extract_zip(content, MAX_ARCHIVE_ENTRIES, remaining_size)?;
+        if files.is_empty() {
+            return Err(unsupported_file(filename));
+        }
+        return Ok(files);
+    }
+
+    Ok(vec![extract_file(filename, content, remaining_size)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    fn gzip(content: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_extract_plain_and_gz_files() {
+        let mut remaining_size = MAX_DECOMPRESSED_SIZE;
+        let files =
+            extract_track_files("../track.gpx", b"<gpx/>".to_vec(), &mut remaining_size).unwrap();
+        assert_eq!(files[0].filename, "track.gpx");
+
+        let files = extract_track_files("track.fit.gz", gzip(b"fit"), &mut remaining_size).unwrap();
+        assert_eq!(files[0].filename, "track.fit");
+        assert_eq!(files[0].content, b"fit");
+        assert_eq!(remaining_size, MAX_DECOMPRESSED_SIZE - 3);
+
+        assert!(extract_track_files("picture.jpg", Vec::new(), &mut remaining_size).is_err());
+        assert!(extract_track_files(".gpx", Vec::new(), &mut remaining_size).is_err());
+    }
+
+    #[test]
+    fn test_extract_gz_files_limit() {
+        // Every file is below the limit but not all the files of the request together
+        let mut remaining_size = 250;
+        assert!(extract_track_files("a.gpx.gz", gzip(&[b' '; 200]), &mut remaining_size).is_ok());
+        let error =
+            extract_track_files("b.gpx.gz", gzip(&[b' '; 100]), &mut remaining_size).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::FileTooLarge);
+    }
+
+    #[test]
+    fn test_extract_zip() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        zip.start_file("tracks/a.gpx", options).unwrap();
+        zip.write_all(b"<gpx/>").unwrap();
+        zip.start_file("notes.txt", options).unwrap();
+        zip.write_all(b"notes").unwrap();
+        let content = zip.finish().unwrap().into_inner();
+
+        let mut remaining_size = MAX_DECOMPRESSED_SIZE;
+        let files = extract_track_files("tracks.zip", content, &mut remaining_size).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].filename, "a.gpx");
+    }
+
+    #[test]
+    fn test_extract_zip_limits() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        for name in ["a.gpx", "b.gpx", "c.gpx"] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(&[b' '; 100]).unwrap();
+        }
+        let content = zip.finish().unwrap().into_inner();
+
+        assert_eq!(extract_zip(content.clone(), 3, &mut 300).unwrap().len(), 3);
+        // Every entry is below the limit but not all of them together
+        assert!(extract_zip(content.clone(), 3, &mut 250).is_err());
+        assert!(extract_zip(content, 2, &mut 300).is_err());
+    }
+
+    #[test]
+    fn test_extract_zip_of_gz_files_limit() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        for name in ["a.gpx.gz", "b.gpx.gz", "c.gpx.gz"] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(&gzip(&[b' '; 1000])).unwrap();
+        }
+        let content = zip.finish().unwrap().into_inner();
+
+        let mut remaining_size = 10_000;
+        assert_eq!(
+            extract_zip(content.clone(), 3, &mut remaining_size)
+                .unwrap()
+                .len(),
+            3
+        );
+        // The compressed entries are small but their content is not
+        let error = extract_zip(content, 3, &mut 2500).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::FileTooLarge);
+    }
+}
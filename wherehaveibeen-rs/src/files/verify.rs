@@ -0,0 +1,73 @@
+use std::{
+    collections::HashSet,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use crate::{
+    database::{heatmap::HeatmapDatabase, tracks::TracksDatabase},
+    utils::{
+        file_access::resolve_path_inside,
+        file_utils::{get_content_hash, get_valid_gps_files, read_binary_file},
+    },
+};
+
+use super::ingest::compute_cached_heatmap;
+
+// A file that can not be found is a problem, any other error is returned
+fn check_file(directory: &Path, filename: &str) -> Result<Option<Vec<u8>>, Error> {
+    match resolve_path_inside(directory, filename).and_then(|path| read_binary_file(&path)) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Check that the database, the cached coordinates and the original files are consistent.
+/// Returns the problems that have been found.
+pub fn verify_tracks(
+    tracks_directory: &Path,
+    cache_directory: &Path,
+) -> Result<Vec<String>, Error> {
+    let tracks_db = TracksDatabase::new().map_err(Error::other)?;
+    let mut problems: Vec<String> = Vec::new();
+
+    for (filename, file_hash) in tracks_db.get_file_hashes().map_err(Error::other)? {
+        match check_file(tracks_directory, &filename)? {
+            None => problems.push(format!("{}: the original file is missing", filename)),
+            Some(content) => {
+                if !file_hash.is_empty() && get_content_hash(&content) != file_hash {
+                    problems.push(format!(
+                        "{}: the original file has changed since it was stored",
+                        filename
+                    ));
+                }
+            }
+        }
+        if check_file(cache_directory, &filename)?.is_none() {
+            problems.push(format!("{}: the cached coordinates are missing", filename));
+        }
+    }
+
+    let stored_files: HashSet<String> = tracks_db.get_all_filenames().into_iter().collect();
+    for filename in get_valid_gps_files(tracks_directory)? {
+        if !stored_files.contains(&filename) {
+            problems.push(format!("{}: the file has not been ingested yet", filename));
+        }
+    }
+
+    // Comparing the totals is enough to detect a heatmap that is out of sync
+    let (heatmap, _) = compute_cached_heatmap(&tracks_db)?;
+    let expected_points = heatmap.len() as i64;
+    let expected_frequency: i64 = heatmap.values().map(|frequency| *frequency as i64).sum();
+    let heatmap_db = HeatmapDatabase::new().map_err(Error::other)?;
+    let (points, frequency) = heatmap_db.get_totals().map_err(Error::other)?;
+    if points != expected_points || frequency != expected_frequency {
+        problems.push(format!(
+            "heatmap: {} points with a total frequency of {} but the tracks have {} points with a total frequency of {}. Run rebuild-heatmap to fix it",
+            points, frequency, expected_points, expected_frequency
+        ));
+    }
+
+    Ok(problems)
+}
@@ -0,0 +1,178 @@
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+
+use crate::database::activity_types::ActivityTypesDatabase;
+use crate::database::metadata::MetadataDatabase;
+use crate::model::activity_type::{
+    validate_activity_type, ActivityTypeMappingUpdate, ActivityTypeUpdate, Reclassification,
+};
+use crate::utils::api_error::ApiError;
+use crate::utils::api_response::json_ok;
+use crate::utils::api_utils::{AdminAccess, ApiPath, ValidatedJson};
+
+fn open_activity_types_database() -> Result<ActivityTypesDatabase, ApiError> {
+    ActivityTypesDatabase::new().map_err(ApiError::database_unavailable)
+}
+
+// The summaries in the GeoJSON responses include the activity type
+fn increment_generation() -> Result<(), ApiError> {
+    let metadata_db = MetadataDatabase::new().map_err(ApiError::database_unavailable)?;
+    metadata_db.increment_generation()?;
+
+    Ok(())
+}
+
+/// Known activity types with their categories and the mappings applied to the raw types found
+/// in the files
+#[utoipa::path(
+    get,
+    path = "/activity-types",
+    tag = "activity-types",
+    responses((status = 200, body = ActivityTypeRegistry))
+)]
+pub async fn get_activity_type_registry() -> Result<impl IntoResponse, ApiError> {
+    let activity_types_db = open_activity_types_database()?;
+    let registry = activity_types_db.get_registry()?;
+
+    Ok(json_ok(registry))
+}
+
+/// Register an activity type or move it to a different category
+#[utoipa::path(
+    put,
+    path = "/activity-types/{name}",
+    tag = "activity-types",
+    params(("name" = String, Path, description = "Name of the activity type")),
+    request_body = ActivityTypeUpdate,
+    responses(
+        (status = 200, body = ActivityTypeRegistry),
+        (status = 400, body = ErrorResponse),
+        (status = 401, description = "The admin token is missing or wrong", body = ErrorResponse),
+        (status = 403, description = "No admin token is configured", body = ErrorResponse),
+    )
+)]
+pub async fn put_activity_type(
+    _: AdminAccess,
+    ApiPath(name): ApiPath<String>,
+    ValidatedJson(update): ValidatedJson<ActivityTypeUpdate>,
+) -> Result<impl IntoResponse, ApiError> {
+    validate_activity_type("name", &name).map_err(ApiError::BadRequest)?;
+    let parent = update.parent.as_deref().map(str::trim);
+
+    let mut activity_types_db = open_activity_types_database()?;
+    if let Some(parent) = parent {
+        if activity_types_db.get_ancestors(parent)?.contains(&name) {
+            return Err(ApiError::BadRequest(format!(
+                "{} can not be a subtype of itself",
+                name
+            )));
+        }
+    }
+    activity_types_db.set_parent(&name, parent)?;
+
+    Ok(json_ok(activity_types_db.get_registry()?))
+}
+
+/// Remove an activity type that is not used by any track or mapping.
+/// Its subtypes are moved to its parent.
+#[utoipa::path(
+    delete,
+    path = "/activity-types/{name}",
+    tag = "activity-types",
+    params(("name" = String, Path, description = "Name of the activity type")),
+    responses(
+        (status = 204, description = "The activity type has been deleted"),
+        (status = 404, body = ErrorResponse),
+        (status = 409, description = "The activity type is in use", body = ErrorResponse),
+        (status = 401, description = "The admin token is missing or wrong", body = ErrorResponse),
+        (status = 403, description = "No admin token is configured", body = ErrorResponse),
+    )
+)]
+pub async fn delete_activity_type(
+    _: AdminAccess,
+    ApiPath(name): ApiPath<String>,
+) -> Result<StatusCode, ApiError> {
+    let mut activity_types_db = open_activity_types_database()?;
+    if activity_types_db.count_uses(&name)? > 0 {
+        return Err(ApiError::Conflict(format!(
+            "{} is used by tracks or mappings",
+            name
+        )));
+    }
+    if !activity_types_db.delete_activity_type(&name)? {
+        return Err(ApiError::NotFound(format!(
+            "The activity type {} does not exist",
+            name
+        )));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Store the raw type as a different activity type. The stored tracks are changed too, except
+/// the ones whose activity type has been set by hand.
+#[utoipa::path(
+    put,
+    path = "/activity-types/mappings/{raw_type}",
+    tag = "activity-types",
+    params(("raw_type" = String, Path, description = "Activity type found in the files")),
+    request_body = ActivityTypeMappingUpdate,
+    responses(
+        (status = 200, body = Reclassification),
+        (status = 400, body = ErrorResponse),
+        (status = 401, description = "The admin token is missing or wrong", body = ErrorResponse),
+        (status = 403, description = "No admin token is configured", body = ErrorResponse),
+    )
+)]
+pub async fn put_activity_type_mapping(
+    _: AdminAccess,
+    ApiPath(raw_type): ApiPath<String>,
+    ValidatedJson(update): ValidatedJson<ActivityTypeMappingUpdate>,
+) -> Result<impl IntoResponse, ApiError> {
+    validate_activity_type("raw_type", &raw_type).map_err(ApiError::BadRequest)?;
+    let activity_type = update.activity_type.trim().to_string();
+
+    let mut activity_types_db = open_activity_types_database()?;
+    let reclassified_tracks = activity_types_db.set_mapping(&raw_type, &activity_type)?;
+    if reclassified_tracks > 0 {
+        increment_generation()?;
+    }
+
+    Ok(json_ok(Reclassification {
+        raw_type,
+        activity_type,
+        reclassified_tracks,
+    }))
+}
+
+/// Remove a mapping. The stored tracks go back to the raw type.
+#[utoipa::path(
+    delete,
+    path = "/activity-types/mappings/{raw_type}",
+    tag = "activity-types",
+    params(("raw_type" = String, Path, description = "Activity type found in the files")),
+    responses(
+        (status = 200, body = Reclassification),
+        (status = 404, body = ErrorResponse),
+        (status = 401, description = "The admin token is missing or wrong", body = ErrorResponse),
+        (status = 403, description = "No admin token is configured", body = ErrorResponse),
+    )
+)]
+pub async fn delete_activity_type_mapping(
+    _: AdminAccess,
+    ApiPath(raw_type): ApiPath<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut activity_types_db = open_activity_types_database()?;
+    let reclassified_tracks = activity_types_db
+        .delete_mapping(&raw_type)?
+        .ok_or_else(|| ApiError::NotFound(format!("There is no mapping for {}", raw_type)))?;
+    if reclassified_tracks > 0 {
+        increment_generation()?;
+    }
+
+    Ok(json_ok(Reclassification {
+        activity_type: raw_type.clone(),
+        raw_type,
+        reclassified_tracks,
+    }))
+}
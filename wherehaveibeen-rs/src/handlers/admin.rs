@@ -0,0 +1,53 @@
+use axum::response::IntoResponse;
+
+use crate::files::ingest::rebuild_heatmap;
+use crate::utils::api_error::ApiError;
+use crate::utils::api_response::json_ok;
+use crate::utils::api_utils::AdminAccess;
+
+/// Compute the heatmap again from the stored tracks. Useful if it is corrupted or the rounding
+/// of the points changes. Requires the admin token as `Authorization: Bearer <token>`.
+#[utoipa::path(
+    post,
+    path = "/admin/rebuild-heatmap",
+    tag = "admin",
+    responses(
+        (status = 200, body = HeatmapRebuild),
+        (status = 401, description = "The admin token is missing or wrong", body = ErrorResponse),
+        (status = 403, description = "No admin token is configured", body = ErrorResponse),
+        (status = 500, body = ErrorResponse),
+    )
+)]
+pub async fn post_rebuild_heatmap(_: AdminAccess) -> Result<impl IntoResponse, ApiError> {
+    // Reading all the tracks can take a while
+    let result = tokio::task::spawn_blocking(rebuild_heatmap)
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+        .map_err(|e| {
+            eprintln!("Error rebuilding the heatmap: {}", e);
+            ApiError::Internal("The heatmap could not be rebuilt".to_string())
+        })?;
+
+    Ok(json_ok(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::Request, http::StatusCode, routing::post, Router};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rebuild_heatmap_disabled_without_token() {
+        // The default configuration has no admin token
+        let app = Router::new().route("/rebuild-heatmap", post(post_rebuild_heatmap));
+        let request = Request::post("/rebuild-heatmap")
+            .header("Authorization", "Bearer anything")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -1,38 +1,60 @@
-use axum::extract::Query;
-use axum::response::IntoResponse;
-use std::collections::HashMap;
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
 
 use crate::database::heatmap::HeatmapDatabase;
-use crate::model::track::TrackInformation;
-use crate::utils::api_response::json_not_found;
-use crate::utils::api_response::json_ok;
-use crate::utils::api_utils::get_query_parameter;
+use crate::database::metadata::MetadataDatabase;
+use crate::model::geojson::FeatureCollection;
+use crate::model::query::{AreaQuery, FormatQuery, ResponseFormat};
+use crate::utils::api_error::ApiError;
+use crate::utils::api_response::{binary_ok, geojson_ok, json_ok};
+use crate::utils::api_utils::{RequestedFormat, ValidatedQuery};
+use crate::utils::encoding::encode_heatmap;
+use crate::utils::http_cache::{CacheHeaders, ALWAYS_REVALIDATE};
 
+/// Points of the heatmap inside the given area of the map.
+/// As GeoJSON each point is a `Point` feature with its `frequency` as property.
+/// As binary each point is a 12 bytes little-endian record: latitude and longitude as `i32`
+/// multiplied by 100000 followed by the frequency as `u32`.
+/// The ETag changes every time new tracks are stored.
+#[utoipa::path(
+    get,
+    path = "/heatmap",
+    tag = "heatmap",
+    params(AreaQuery, FormatQuery),
+    responses(
+        (status = 200, content(
+            ("application/json" = Vec<HeatmapCoordinate>),
+            ("application/geo+json" = Object),
+            ("application/octet-stream" = Vec<u8>),
+        )),
+        (status = 304, description = "The heatmap has not changed since the given ETag"),
+        (status = 400, body = ErrorResponse),
+    )
+)]
 pub async fn get_filtered_heatmap(
-    Query(params): Query<HashMap<String, String>>,
-) -> impl IntoResponse {
-    let north_west_latitude: f32 = get_query_parameter(&params, "northWestLatitude");
-    let north_west_longitude: f32 = get_query_parameter(&params, "northWestLongitude");
-    let south_east_latitude: f32 = get_query_parameter(&params, "southEastLatitude");
-    let south_east_longitude: f32 = get_query_parameter(&params, "southEastLongitude");
-
-    let track_information = TrackInformation::new(
-        north_west_latitude,
-        north_west_longitude,
-        south_east_latitude,
-        south_east_longitude,
-        "".to_string(), // date is not implemented yet
-        "".to_string(), // activity type is not available for heatmap
-    );
+    ValidatedQuery(area): ValidatedQuery<AreaQuery>,
+    RequestedFormat(format): RequestedFormat,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    if format == ResponseFormat::Polyline {
+        return Err(ApiError::unsupported_format());
+    }
 
-    let heatmap_db = HeatmapDatabase::new().unwrap();
-    match heatmap_db.get_heatmap_inside_location(track_information) {
-        Ok(coordinates) => {
-            return json_ok(&coordinates).into_response();
-        }
-        Err(e) => {
-            println!("Error: {}", e);
-            return json_not_found("No points where found in the given area").into_response();
-        }
+    let metadata_db = MetadataDatabase::new().map_err(ApiError::database_unavailable)?;
+    let generation = metadata_db.get_generation()?.to_string();
+    let cache = CacheHeaders::new(&["heatmap", &generation, format.name()], ALWAYS_REVALIDATE);
+    if cache.is_fresh(&headers) {
+        return Ok(cache.not_modified());
     }
+
+    let heatmap_db = HeatmapDatabase::new().map_err(ApiError::database_unavailable)?;
+    let coordinates = heatmap_db.get_heatmap_inside_location(&area.bounds())?;
+
+    let response = match format {
+        ResponseFormat::GeoJson => geojson_ok(FeatureCollection::from(coordinates)).into_response(),
+        ResponseFormat::Binary => binary_ok(encode_heatmap(&coordinates)).into_response(),
+        _ => json_ok(coordinates).into_response(),
+    };
+
+    Ok(cache.apply(response))
 }
@@ -0,0 +1,152 @@
+use std::io::ErrorKind;
+
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+
+use crate::database::layers::LayersDatabase;
+use crate::database::metadata::MetadataDatabase;
+use crate::database::tracks::TracksDatabase;
+use crate::files::ingest::remove_file_without_track;
+use crate::model::geojson::{Feature, FeatureCollection};
+use crate::model::layer::{RouteList, WaypointList};
+use crate::model::query::{AreaQuery, FormatQuery, ResponseFormat};
+use crate::utils::api_error::ApiError;
+use crate::utils::api_response::{geojson_ok, json_ok};
+use crate::utils::api_utils::{AdminAccess, ApiPath, RequestedFormat, ValidatedQuery};
+use crate::utils::http_cache::{CacheHeaders, ALWAYS_REVALIDATE};
+
+fn open_layers_database() -> Result<LayersDatabase, ApiError> {
+    LayersDatabase::new().map_err(ApiError::database_unavailable)
+}
+
+// The layers change with the same generation as the tracks
+fn get_cache_headers(layer: &str, format: ResponseFormat) -> Result<CacheHeaders, ApiError> {
+    let metadata_db = MetadataDatabase::new().map_err(ApiError::database_unavailable)?;
+    let generation = metadata_db.get_generation()?.to_string();
+
+    Ok(CacheHeaders::new(
+        &[layer, &generation, format.name()],
+        ALWAYS_REVALIDATE,
+    ))
+}
+
+/// Planned routes found in the GPX files whose bounding box intersects the given area.
+/// As GeoJSON each route is a `LineString` feature with its summary as properties.
+/// The ETag changes every time the stored files change.
+#[utoipa::path(
+    get,
+    path = "/routes",
+    tag = "layers",
+    params(AreaQuery, FormatQuery),
+    responses(
+        (status = 200, content(
+            ("application/json" = RouteList),
+            ("application/geo+json" = Object),
+        )),
+        (status = 304, description = "The routes have not changed since the given ETag"),
+        (status = 400, body = ErrorResponse),
+    )
+)]
+pub async fn get_routes(
+    ValidatedQuery(area): ValidatedQuery<AreaQuery>,
+    RequestedFormat(format): RequestedFormat,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    if matches!(format, ResponseFormat::Polyline | ResponseFormat::Binary) {
+        return Err(ApiError::unsupported_format());
+    }
+    let cache = get_cache_headers("routes", format)?;
+    if cache.is_fresh(&headers) {
+        return Ok(cache.not_modified());
+    }
+
+    let layers_db = open_layers_database()?;
+    let routes = layers_db.get_routes(&area.bounds())?;
+
+    let response = match format {
+        ResponseFormat::GeoJson => {
+            let features = routes.into_iter().map(Feature::from).collect();
+            geojson_ok(FeatureCollection { features }).into_response()
+        }
+        _ => json_ok(RouteList { routes }).into_response(),
+    };
+
+    Ok(cache.apply(response))
+}
+
+/// Waypoints found in the GPX files inside the given area, like summits, huts or campsites.
+/// As GeoJSON each waypoint is a `Point` feature with all its fields as properties.
+/// The ETag changes every time the stored files change.
+#[utoipa::path(
+    get,
+    path = "/waypoints",
+    tag = "layers",
+    params(AreaQuery, FormatQuery),
+    responses(
+        (status = 200, content(
+            ("application/json" = WaypointList),
+            ("application/geo+json" = Object),
+        )),
+        (status = 304, description = "The waypoints have not changed since the given ETag"),
+        (status = 400, body = ErrorResponse),
+    )
+)]
+pub async fn get_waypoints(
+    ValidatedQuery(area): ValidatedQuery<AreaQuery>,
+    RequestedFormat(format): RequestedFormat,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    if matches!(format, ResponseFormat::Polyline | ResponseFormat::Binary) {
+        return Err(ApiError::unsupported_format());
+    }
+    let cache = get_cache_headers("waypoints", format)?;
+    if cache.is_fresh(&headers) {
+        return Ok(cache.not_modified());
+    }
+
+    let layers_db = open_layers_database()?;
+    let waypoints = layers_db.get_waypoints(&area.bounds())?;
+
+    let response = match format {
+        ResponseFormat::GeoJson => {
+            let features = waypoints.into_iter().map(Feature::from).collect();
+            geojson_ok(FeatureCollection { features }).into_response()
+        }
+        _ => json_ok(WaypointList { waypoints }).into_response(),
+    };
+
+    Ok(cache.apply(response))
+}
+
+/// Remove a GPX file that only has routes and waypoints, together with them. The id is the
+/// `fileId` of its routes and waypoints. Files with a track are removed with `DELETE /tracks/{id}`.
+#[utoipa::path(
+    delete,
+    path = "/layers/{id}",
+    tag = "layers",
+    params(("id" = i64, Path, description = "Id of the GPX file")),
+    responses(
+        (status = 204, description = "The file has been deleted"),
+        (status = 404, body = ErrorResponse),
+        (status = 401, description = "The admin token is missing or wrong", body = ErrorResponse),
+        (status = 403, description = "No admin token is configured", body = ErrorResponse),
+    )
+)]
+pub async fn delete_layer_file(
+    _: AdminAccess,
+    ApiPath(id): ApiPath<i64>,
+) -> Result<StatusCode, ApiError> {
+    let mut tracks_db = TracksDatabase::new().map_err(ApiError::database_unavailable)?;
+
+    remove_file_without_track(&mut tracks_db, id).map_err(|e| {
+        eprintln!("Error deleting the file {}: {}", id, e);
+        match e.kind() {
+            ErrorKind::NotFound => ApiError::NotFound(
+                "There is no file with only routes and waypoints with this id".to_string(),
+            ),
+            _ => ApiError::Internal("The file could not be deleted".to_string()),
+        }
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
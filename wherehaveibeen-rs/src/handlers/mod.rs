@@ -1,2 +1,7 @@
+pub mod activity_types;
+pub mod admin;
+pub mod heatmap;
+pub mod openapi;
+pub mod stats;
 pub mod tracks;
-pub mod heatmap;
\ No newline at end of file
+pub mod layers;
\ No newline at end of file
@@ -0,0 +1,115 @@
+use axum::response::IntoResponse;
+use utoipa::OpenApi;
+
+use crate::handlers::{activity_types, admin, heatmap, layers, stats, tracks};
+use crate::model::activity_type::{
+    ActivityTypeEntry, ActivityTypeMapping, ActivityTypeMappingUpdate, ActivityTypeRegistry,
+    ActivityTypeUpdate, Reclassification,
+};
+use crate::model::coordinate::Coordinate;
+use crate::model::filter::Bounds;
+use crate::model::heatmap::{HeatmapCoordinate, HeatmapRebuild};
+use crate::model::layer::{Route, RouteList, RouteSummary, Waypoint, WaypointList};
+use crate::model::stats::{Statistics, StatisticsGroup};
+use crate::model::stream::{ProfileSample, TrackProfile, TrackStreams};
+use crate::model::track::{
+    ActivityTypeList, EncodedTrack, RejectedFile, TrackList, TrackMetrics, TrackReference,
+    TrackReferenceList, TrackSession, TrackSummary, TrackUpdate, UploadResult,
+};
+use crate::utils::api_error::ErrorResponse;
+use crate::utils::api_response::json_ok;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        tracks::get_tracks,
+        tracks::upload_tracks,
+        tracks::get_activity_types,
+        tracks::get_filtered_tracks,
+        tracks::get_track,
+        tracks::update_track,
+        tracks::delete_track,
+        tracks::get_track_summary,
+        tracks::get_track_streams,
+        tracks::get_track_profile,
+        tracks::get_track_coordinates,
+        tracks::get_tracks_export,
+        heatmap::get_filtered_heatmap,
+        stats::get_statistics,
+        activity_types::get_activity_type_registry,
+        activity_types::put_activity_type,
+        activity_types::delete_activity_type,
+        activity_types::put_activity_type_mapping,
+        activity_types::delete_activity_type_mapping,
+        admin::post_rebuild_heatmap,
+        layers::get_routes,
+        layers::get_waypoints,
+        layers::delete_layer_file,
+    ),
+    components(schemas(
+        Coordinate,
+        HeatmapCoordinate,
+        HeatmapRebuild,
+        Bounds,
+        TrackMetrics,
+        TrackReference,
+        TrackReferenceList,
+        TrackSummary,
+        TrackSession,
+        TrackStreams,
+        TrackProfile,
+        ProfileSample,
+        TrackList,
+        TrackUpdate,
+        ActivityTypeList,
+        EncodedTrack,
+        UploadResult,
+        RejectedFile,
+        Statistics,
+        StatisticsGroup,
+        ActivityTypeEntry,
+        ActivityTypeMapping,
+        ActivityTypeRegistry,
+        ActivityTypeUpdate,
+        ActivityTypeMappingUpdate,
+        Reclassification,
+        RouteSummary,
+        Route,
+        RouteList,
+        Waypoint,
+        WaypointList,
+        ErrorResponse,
+    )),
+    tags(
+        (name = "tracks"),
+        (name = "heatmap"),
+        (name = "stats"),
+        (name = "activity-types"),
+        (name = "admin"),
+        (name = "layers"),
+    )
+)]
+pub struct ApiDoc;
+
+pub async fn get_openapi_specification() -> impl IntoResponse {
+    json_ok(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_field_names_are_documented() {
+        let specification = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let schemas = &specification["components"]["schemas"];
+
+        for field in ["a", "o", "f"] {
+            assert!(schemas["HeatmapCoordinate"]["properties"][field].is_object());
+        }
+        for field in ["a", "o"] {
+            assert!(schemas["Coordinate"]["properties"][field].is_object());
+        }
+        assert!(specification["paths"]["/tracks/{id}/summary"].is_object());
+    }
+}
@@ -0,0 +1,19 @@
+use axum::response::IntoResponse;
+
+use crate::database::tracks::TracksDatabase;
+use crate::utils::api_error::ApiError;
+use crate::utils::api_response::json_ok;
+
+/// Totals of all the tracks grouped by year, month, ISO week and activity type
+#[utoipa::path(
+    get,
+    path = "/stats",
+    tag = "stats",
+    responses((status = 200, body = Statistics))
+)]
+pub async fn get_statistics() -> Result<impl IntoResponse, ApiError> {
+    let tracks_db = TracksDatabase::new().map_err(ApiError::database_unavailable)?;
+    let statistics = tracks_db.get_statistics()?;
+
+    Ok(json_ok(statistics))
+}
@@ -1,110 +1,610 @@
-use axum::extract::Path;
-use axum::extract::Query;
-use axum::http::StatusCode;
+use axum::body::Body;
+use axum::extract::multipart::MultipartError;
+use axum::extract::Multipart;
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
 use axum::response::Response;
-use serde_json::json;
-use std::collections::HashMap;
+use axum::Json;
+use std::io::ErrorKind;
 use std::path::Path as FilePath;
 
+use crate::database::metadata::MetadataDatabase;
 use crate::database::tracks::TracksDatabase;
-use crate::model::track::TrackInformation;
-use crate::utils::api_response::json_not_found;
-use crate::utils::api_response::json_ok;
-use crate::utils::api_utils::get_query_parameter;
-use crate::utils::cache_utils::read_cached_coordinates;
-use crate::utils::environment::get_cache_directory;
-use crate::utils::environment::get_tracks_directory;
-use crate::utils::file_utils::get_valid_gps_files;
-use crate::utils::file_utils::read_file;
-
-pub async fn get_tracks() -> impl IntoResponse {
-    let tracks_directory = get_tracks_directory();
-    let path = FilePath::new(&tracks_directory);
-    match get_valid_gps_files(path) {
-        Ok(files) => {
-            return json_ok(json!({ "fileList": files })).into_response();
-        }
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            return json_not_found("No tracks could be found").into_response();
-        }
+use crate::files::export::export_tracks;
+use crate::files::ingest::{ingest_uploaded_files, remove_track};
+use crate::files::profile::extract_track_profile;
+use crate::files::streams::extract_track_streams;
+use crate::files::upload::{
+    extract_track_files, UploadedFile, MAX_DECOMPRESSED_SIZE, MAX_UPLOAD_SIZE,
+};
+use crate::model::coordinate::Coordinate;
+use crate::model::geojson::{Feature, FeatureCollection};
+use crate::model::query::{
+    ExportQuery, FormatQuery, ProfileQuery, ResponseFormat, TrackFilterQuery, TrackListQuery,
+};
+use crate::model::track::{
+    ActivityTypeList, EncodedTrack, TrackReferenceList, TrackUpdate, UploadRejection,
+};
+use crate::utils::api_error::ApiError;
+use crate::utils::api_response::{geojson_ok, json_ok};
+use crate::utils::api_utils::{
+    AdminAccess, ApiPath, RequestedFormat, ValidatedJson, ValidatedQuery,
+};
+use crate::utils::cache_utils::{parse_cached_coordinates, parse_cached_track_points};
+use crate::utils::config::get_cache_directory;
+use crate::utils::config::get_tracks_directory;
+use crate::utils::encoding::encode_polyline;
+use crate::utils::file_access::get_content_type;
+use crate::utils::file_access::resolve_path_inside;
+use crate::utils::file_utils::{get_content_hash, read_binary_file, read_file};
+use crate::utils::http_cache::{CacheHeaders, ALWAYS_REVALIDATE, CACHE_FOR_ONE_DAY};
+
+fn open_tracks_database() -> Result<TracksDatabase, ApiError> {
+    TracksDatabase::new().map_err(ApiError::database_unavailable)
+}
+
+// Files that are registered but can not be read are reported as not found
+fn map_file_error(error: std::io::Error) -> ApiError {
+    eprintln!("Error: {}", error);
+    match error.kind() {
+        ErrorKind::NotFound | ErrorKind::PermissionDenied => ApiError::track_not_found(),
+        _ => ApiError::Internal("The track could not be read".to_string()),
     }
 }
 
+/// Tracks sorted and paginated. Use `nextCursor` to request the next page.
+#[utoipa::path(
+    get,
+    path = "/tracks",
+    tag = "tracks",
+    params(TrackFilterQuery, TrackListQuery),
+    responses(
+        (status = 200, body = TrackList),
+        (status = 400, body = ErrorResponse),
+    )
+)]
+pub async fn get_tracks(
+    ValidatedQuery(filter): ValidatedQuery<TrackFilterQuery>,
+    ValidatedQuery(list_options): ValidatedQuery<TrackListQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let tracks_db = open_tracks_database()?;
+    let track_list = tracks_db.get_track_list(&filter.to_filter(), &list_options.to_options())?;
+
+    Ok(json_ok(track_list))
+}
+
+fn read_track_cache(filename: &str) -> Result<String, ApiError> {
+    let cache_directory = get_cache_directory();
+    let path =
+        resolve_path_inside(FilePath::new(&cache_directory), filename).map_err(map_file_error)?;
+
+    read_file(&path).map_err(map_file_error)
+}
+
+fn read_track_coordinates(filename: &str) -> Result<Vec<Coordinate>, ApiError> {
+    let content = read_track_cache(filename)?;
+
+    Ok(parse_cached_coordinates(&content))
+}
+
+/// Tracks crossing the given area of the map. The area is required.
+/// As GeoJSON each track is a `LineString` feature with its summary as properties.
+#[utoipa::path(
+    get,
+    path = "/tracks/filtered-tracks",
+    tag = "tracks",
+    params(TrackFilterQuery, FormatQuery),
+    responses(
+        (status = 200, content(
+            ("application/json" = TrackReferenceList),
+            ("application/geo+json" = Object),
+        )),
+        (status = 400, body = ErrorResponse),
+    )
+)]
 pub async fn get_filtered_tracks(
-    Query(params): Query<HashMap<String, String>>,
-) -> impl IntoResponse {
-    let north_west_latitude: f32 = get_query_parameter(&params, "northWestLatitude");
-    let north_west_longitude: f32 = get_query_parameter(&params, "northWestLongitude");
-    let south_east_latitude: f32 = get_query_parameter(&params, "southEastLatitude");
-    let south_east_longitude: f32 = get_query_parameter(&params, "southEastLongitude");
-    let activity_type: String = get_query_parameter(&params, "activityType");
-
-    let track_information = TrackInformation::new(
-        north_west_latitude,
-        north_west_longitude,
-        south_east_latitude,
-        south_east_longitude,
-        "".to_string(), // date is not implemented yet
-        activity_type,
-    );
-    dbg!(&track_information);
+    ValidatedQuery(filter): ValidatedQuery<TrackFilterQuery>,
+    RequestedFormat(format): RequestedFormat,
+) -> Result<Response, ApiError> {
+    if !filter.has_bounds() {
+        return Err(ApiError::BadRequest(
+            "The area of the map must be provided".to_string(),
+        ));
+    }
 
-    let tracks_db = TracksDatabase::new().unwrap();
-    match tracks_db.get_tracks_inside_location(track_information) {
-        Ok(files) => {
-            return json_ok(json!({ "fileList": files })).into_response();
+    let tracks_db = open_tracks_database()?;
+    match format {
+        ResponseFormat::Json => {
+            let tracks = tracks_db.get_filtered_tracks(&filter.to_filter())?;
+            Ok(json_ok(TrackReferenceList { tracks }).into_response())
         }
-        Err(e) => {
-            println!("Error: {}", e);
-            return json_not_found("No tracks could be found").into_response();
+        ResponseFormat::GeoJson => {
+            let summaries = tracks_db.get_filtered_track_summaries(&filter.to_filter())?;
+            // Tracks whose cached coordinates can not be read are left out
+            let features = summaries
+                .into_iter()
+                .filter_map(|summary| {
+                    let coordinates = read_track_coordinates(&summary.filename).ok()?;
+                    Some(Feature::from_track(summary, &coordinates))
+                })
+                .collect();
+            Ok(geojson_ok(FeatureCollection { features }).into_response())
         }
+        ResponseFormat::Polyline | ResponseFormat::Binary => Err(ApiError::unsupported_format()),
     }
 }
 
-pub async fn get_activity_types() -> impl IntoResponse {
-    let tracks_db = TracksDatabase::new().unwrap();
-    match tracks_db.get_all_activity_types() {
-        Ok(activity_types) => {
-            return json_ok(json!({ "activityTypes": activity_types })).into_response();
-        }
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            return json_not_found("No tracks could be found").into_response();
-        }
-    }
+#[utoipa::path(
+    get,
+    path = "/tracks/activity-types",
+    tag = "tracks",
+    responses((status = 200, body = ActivityTypeList))
+)]
+pub async fn get_activity_types() -> Result<impl IntoResponse, ApiError> {
+    let tracks_db = open_tracks_database()?;
+    let activity_types = tracks_db.get_all_activity_types()?;
+
+    Ok(json_ok(ActivityTypeList { activity_types }))
 }
 
-pub async fn get_track(Path(filename): Path<String>) -> impl IntoResponse {
+fn get_registered_filename(id: i64) -> Result<String, ApiError> {
+    let tracks_db = open_tracks_database()?;
+    tracks_db
+        .get_track_filename(id)?
+        .ok_or_else(ApiError::track_not_found)
+}
+
+/// Original file of the track
+#[utoipa::path(
+    get,
+    path = "/tracks/{id}",
+    tag = "tracks",
+    params(("id" = i64, Path, description = "Id of the track")),
+    responses(
+        (status = 200, description = "GPX, TCX or FIT file", body = Vec<u8>,
+            content_type = "application/octet-stream"),
+        (status = 404, body = ErrorResponse),
+    )
+)]
+pub async fn get_track(ApiPath(id): ApiPath<i64>) -> Result<impl IntoResponse, ApiError> {
+    let filename = get_registered_filename(id)?;
+
     let tracks_directory = get_tracks_directory();
-    let path = FilePath::new(&tracks_directory);
-    match read_file(path.join(&filename).as_path()) {
-        Ok(file) => {
-            return Response::builder()
-                .status(StatusCode::OK)
-                .header("Content-Type", "application/gpx+xml")
-                .body(file)
-                .unwrap()
-                .into_response();
+    let path =
+        resolve_path_inside(FilePath::new(&tracks_directory), &filename).map_err(map_file_error)?;
+    let content = read_binary_file(&path).map_err(map_file_error)?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", get_content_type(&path))
+        .body(Body::from(content))
+        .unwrap())
+}
+
+/// Simplified coordinates of the track.
+/// As GeoJSON the track is a `LineString` feature with its summary as properties.
+/// As polyline the coordinates are encoded with the Google encoded polyline algorithm.
+/// The ETag is based on the hash of the coordinates.
+#[utoipa::path(
+    get,
+    path = "/tracks/coordinates/{id}",
+    tag = "tracks",
+    params(("id" = i64, Path, description = "Id of the track"), FormatQuery),
+    responses(
+        (status = 200, content(
+            ("application/json" = Vec<Coordinate>),
+            ("application/geo+json" = Object),
+        )),
+        (status = 304, description = "The track has not changed since the given ETag"),
+        (status = 404, body = ErrorResponse),
+    )
+)]
+pub async fn get_track_coordinates(
+    ApiPath(id): ApiPath<i64>,
+    RequestedFormat(format): RequestedFormat,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    if format == ResponseFormat::Binary {
+        return Err(ApiError::unsupported_format());
+    }
+
+    let tracks_db = open_tracks_database()?;
+    let summary = tracks_db
+        .get_track_summary(id)?
+        .ok_or_else(ApiError::track_not_found)?;
+    let content = read_track_cache(&summary.filename)?;
+    let hash = get_content_hash(content.as_bytes());
+
+    let cache = if format == ResponseFormat::GeoJson {
+        // The properties of the feature can change even if the coordinates do not
+        let metadata_db = MetadataDatabase::new().map_err(ApiError::database_unavailable)?;
+        let generation = metadata_db.get_generation()?.to_string();
+        CacheHeaders::new(&[&hash, format.name(), &generation], ALWAYS_REVALIDATE)
+    } else {
+        CacheHeaders::new(&[&hash, format.name()], CACHE_FOR_ONE_DAY)
+    };
+    if cache.is_fresh(&headers) {
+        return Ok(cache.not_modified());
+    }
+
+    let coordinates = parse_cached_coordinates(&content);
+    let response = match format {
+        ResponseFormat::GeoJson => {
+            geojson_ok(Feature::from_track(summary, &coordinates)).into_response()
         }
-        Err(e) => {
-            println!("Error: {}", e);
-            return json_not_found("Thr provided track could be found").into_response();
+        ResponseFormat::Polyline => {
+            let polyline = encode_polyline(&coordinates);
+            json_ok(EncodedTrack { polyline }).into_response()
         }
+        _ => json_ok(coordinates).into_response(),
+    };
+
+    Ok(cache.apply(response))
+}
+
+/// Distance, time, elevation and sensor values of each point of the track. Tracks stored by older
+/// versions only have the distance until they are ingested again.
+#[utoipa::path(
+    get,
+    path = "/tracks/{id}/streams",
+    tag = "tracks",
+    params(("id" = i64, Path, description = "Id of the track")),
+    responses(
+        (status = 200, body = TrackStreams),
+        (status = 304, description = "The track has not changed since the given ETag"),
+        (status = 404, body = ErrorResponse),
+    )
+)]
+pub async fn get_track_streams(
+    ApiPath(id): ApiPath<i64>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let filename = get_registered_filename(id)?;
+    let content = read_track_cache(&filename)?;
+
+    let cache = CacheHeaders::new(
+        &[&get_content_hash(content.as_bytes()), "streams"],
+        CACHE_FOR_ONE_DAY,
+    );
+    if cache.is_fresh(&headers) {
+        return Ok(cache.not_modified());
+    }
+
+    let streams = extract_track_streams(&parse_cached_track_points(&content));
+
+    Ok(cache.apply(json_ok(streams).into_response()))
+}
+
+/// Elevation, speed, grade and time along the track, for elevation charts. The samples are taken
+/// at equal distances.
+#[utoipa::path(
+    get,
+    path = "/tracks/{id}/profile",
+    tag = "tracks",
+    params(("id" = i64, Path, description = "Id of the track"), ProfileQuery),
+    responses(
+        (status = 200, body = TrackProfile),
+        (status = 304, description = "The track has not changed since the given ETag"),
+        (status = 400, body = ErrorResponse),
+        (status = 404, body = ErrorResponse),
+    )
+)]
+pub async fn get_track_profile(
+    ApiPath(id): ApiPath<i64>,
+    ValidatedQuery(query): ValidatedQuery<ProfileQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let filename = get_registered_filename(id)?;
+    let content = read_track_cache(&filename)?;
+    let points = query.get_points();
+
+    let cache = CacheHeaders::new(
+        &[
+            &get_content_hash(content.as_bytes()),
+            "profile",
+            &points.to_string(),
+        ],
+        CACHE_FOR_ONE_DAY,
+    );
+    if cache.is_fresh(&headers) {
+        return Ok(cache.not_modified());
     }
+
+    let profile = extract_track_profile(&parse_cached_track_points(&content), points);
+
+    Ok(cache.apply(json_ok(profile).into_response()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/tracks/{id}/summary",
+    tag = "tracks",
+    params(("id" = i64, Path, description = "Id of the track")),
+    responses(
+        (status = 200, body = TrackSummary),
+        (status = 404, body = ErrorResponse),
+    )
+)]
+pub async fn get_track_summary(ApiPath(id): ApiPath<i64>) -> Result<impl IntoResponse, ApiError> {
+    let tracks_db = open_tracks_database()?;
+    let summary = tracks_db
+        .get_track_summary(id)?
+        .ok_or_else(ApiError::track_not_found)?;
+
+    Ok(json_ok(summary))
+}
+
+/// Change the activity type or the name of the track
+#[utoipa::path(
+    patch,
+    path = "/tracks/{id}",
+    tag = "tracks",
+    params(("id" = i64, Path, description = "Id of the track")),
+    request_body = TrackUpdate,
+    responses(
+        (status = 200, body = TrackSummary),
+        (status = 400, body = ErrorResponse),
+        (status = 404, body = ErrorResponse),
+        (status = 401, description = "The admin token is missing or wrong", body = ErrorResponse),
+        (status = 403, description = "No admin token is configured", body = ErrorResponse),
+    )
+)]
+pub async fn update_track(
+    _: AdminAccess,
+    ApiPath(id): ApiPath<i64>,
+    ValidatedJson(update): ValidatedJson<TrackUpdate>,
+) -> Result<impl IntoResponse, ApiError> {
+    let tracks_db = open_tracks_database()?;
+    let activity_type = update.activity_type.as_deref().map(str::trim);
+    let name = update.name.as_deref().map(str::trim);
+    if !tracks_db.update_track(id, activity_type, name)? {
+        return Err(ApiError::track_not_found());
+    }
+
+    // The summaries in the GeoJSON responses have changed
+    let metadata_db = MetadataDatabase::new().map_err(ApiError::database_unavailable)?;
+    metadata_db.increment_generation()?;
+
+    let summary = tracks_db
+        .get_track_summary(id)?
+        .ok_or_else(ApiError::track_not_found)?;
+
+    Ok(json_ok(summary))
 }
 
-pub async fn get_track_coordinates(Path(filename): Path<String>) -> impl IntoResponse {
+/// Remove the track, its original file and its points from the heatmap
+#[utoipa::path(
+    delete,
+    path = "/tracks/{id}",
+    tag = "tracks",
+    params(("id" = i64, Path, description = "Id of the track")),
+    responses(
+        (status = 204, description = "The track has been deleted"),
+        (status = 404, body = ErrorResponse),
+        (status = 401, description = "The admin token is missing or wrong", body = ErrorResponse),
+        (status = 403, description = "No admin token is configured", body = ErrorResponse),
+    )
+)]
+pub async fn delete_track(
+    _: AdminAccess,
+    ApiPath(id): ApiPath<i64>,
+) -> Result<StatusCode, ApiError> {
+    let mut tracks_db = open_tracks_database()?;
+    let summary = tracks_db
+        .get_track_summary(id)?
+        .ok_or_else(ApiError::track_not_found)?;
+
+    remove_track(&mut tracks_db, &summary).map_err(|e| {
+        eprintln!("Error deleting the track {}: {}", id, e);
+        match e.kind() {
+            ErrorKind::NotFound => ApiError::track_not_found(),
+            _ => ApiError::Internal("The track could not be deleted".to_string()),
+        }
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// All the tracks matching the filters in a single file
+#[utoipa::path(
+    get,
+    path = "/tracks/export",
+    tag = "tracks",
+    params(TrackFilterQuery, ExportQuery),
+    responses(
+        (status = 200, content(
+            ("application/gpx+xml" = String),
+            ("application/vnd.google-earth.kml+xml" = String),
+            ("application/zip" = Vec<u8>),
+        )),
+        (status = 400, body = ErrorResponse),
+    )
+)]
+pub async fn get_tracks_export(
+    ValidatedQuery(filter): ValidatedQuery<TrackFilterQuery>,
+    ValidatedQuery(export): ValidatedQuery<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let tracks_db = open_tracks_database()?;
+    let tracks = tracks_db.get_filtered_track_summaries(&filter.to_filter())?;
+
+    let tracks_directory = get_tracks_directory();
     let cache_directory = get_cache_directory();
-    let cache_path = FilePath::new(&cache_directory);
-    match read_cached_coordinates(cache_path.join(&filename).as_path()) {
-        Ok(coordinates) => {
-            return json_ok(&coordinates).into_response();
+    let content = export_tracks(
+        tracks,
+        export.format,
+        FilePath::new(&tracks_directory),
+        FilePath::new(&cache_directory),
+    )
+    .map_err(map_file_error)?;
+
+    let disposition = format!("attachment; filename=\"{}\"", export.format.filename());
+    Ok((
+        [
+            (CONTENT_TYPE, export.format.content_type().to_string()),
+            (CONTENT_DISPOSITION, disposition),
+        ],
+        content,
+    )
+        .into_response())
+}
+
+fn map_multipart_error(error: MultipartError) -> ApiError {
+    if error.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        ApiError::PayloadTooLarge(format!(
+            "The upload can not be larger than {} MB",
+            MAX_UPLOAD_SIZE / 1024 / 1024
+        ))
+    } else {
+        ApiError::BadRequest(error.body_text())
+    }
+}
+
+/// Upload GPX, FIT or TCX files. They can be compressed with gzip or bundled in a ZIP archive.
+/// Files that are already stored or can not be parsed are rejected.
+#[utoipa::path(
+    post,
+    path = "/tracks",
+    tag = "tracks",
+    request_body(
+        content = String,
+        content_type = "multipart/form-data",
+        description = "One or more files"
+    ),
+    responses(
+        (status = 201, body = UploadResult),
+        (status = 400, body = ErrorResponse),
+        (status = 409, description = "All the files are already stored", body = ErrorResponse),
+        (status = 413, body = ErrorResponse),
+        (status = 401, description = "The admin token is missing or wrong", body = ErrorResponse),
+        (status = 403, description = "No admin token is configured", body = ErrorResponse),
+    )
+)]
+pub async fn upload_tracks(_: AdminAccess, mut multipart: Multipart) -> Result<Response, ApiError> {
+    let mut files: Vec<UploadedFile> = Vec::new();
+    // Shared by all the files of the request
+    let mut remaining_size = MAX_DECOMPRESSED_SIZE;
+    while let Some(field) = multipart.next_field().await.map_err(map_multipart_error)? {
+        // Fields that are not files are ignored
+        let Some(filename) = field.file_name().map(str::to_string) else {
+            continue;
+        };
+        let content = field.bytes().await.map_err(map_multipart_error)?;
+        let track_files = extract_track_files(&filename, content.to_vec(), &mut remaining_size)
+            .map_err(|e| match e.kind() {
+                ErrorKind::FileTooLarge => ApiError::PayloadTooLarge(e.to_string()),
+                _ => ApiError::BadRequest(e.to_string()),
+            })?;
+        files.extend(track_files);
+    }
+    if files.is_empty() {
+        return Err(ApiError::BadRequest("No file was uploaded".to_string()));
+    }
+
+    // Parsing the files can take a while
+    let result = tokio::task::spawn_blocking(move || ingest_uploaded_files(files))
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+        .map_err(|e| {
+            eprintln!("Error storing the uploaded files: {}", e);
+            ApiError::Internal("The files could not be stored".to_string())
+        })?;
+
+    if result.tracks.is_empty() {
+        let reasons: Vec<String> = result
+            .rejected_files
+            .iter()
+            .map(|file| format!("{}: {}", file.filename, file.reason))
+            .collect();
+        let all_duplicated = result
+            .rejected_files
+            .iter()
+            .all(|file| matches!(file.reason, UploadRejection::Duplicate(_)));
+        return Err(if all_duplicated {
+            ApiError::Conflict(reasons.join(", "))
+        } else {
+            ApiError::BadRequest(reasons.join(", "))
+        });
+    }
+
+    Ok((StatusCode::CREATED, Json(result)).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::Request,
+        routing::{delete, get},
+        Router,
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::database::migrations::initialize_database;
+    use crate::model::track::TrackInformation;
+    use crate::utils::config::get_database_path;
+
+    async fn get_status(app: &Router, uri: &str) -> StatusCode {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        app.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_get_unknown_or_empty_track() {
+        // The tests use a database in the temporary directory
+        initialize_database().unwrap();
+        let tracks_db = open_tracks_database().unwrap();
+        let empty_track_id = tracks_db
+            .insert_new_file(
+                "empty.gpx",
+                TrackInformation::create_empty_track(),
+                true,
+                "empty",
+            )
+            .unwrap();
+
+        let app = Router::new()
+            .route("/:id", get(get_track))
+            .route("/coordinates/:id", get(get_track_coordinates))
+            .route("/:id/streams", get(get_track_streams))
+            .route("/:id/profile", get(get_track_profile))
+            .route("/:id/summary", get(get_track_summary));
+        for id in [empty_track_id, empty_track_id + 1] {
+            for uri in [
+                format!("/{}", id),
+                format!("/coordinates/{}", id),
+                format!("/{}/streams", id),
+                format!("/{}/profile", id),
+                format!("/{}/summary", id),
+            ] {
+                assert_eq!(
+                    get_status(&app, &uri).await,
+                    StatusCode::NOT_FOUND,
+                    "{}",
+                    uri
+                );
+            }
         }
-        Err(e) => {
-            println!("Error: {}", e);
-            return json_not_found("Thr provided track could be found").into_response();
+        assert_eq!(get_status(&app, "/a.gpx").await, StatusCode::BAD_REQUEST);
+
+        std::fs::remove_file(get_database_path()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_changes_disabled_without_token() {
+        // The default configuration has no admin token
+        let app = Router::new().route("/:id", delete(delete_track).patch(update_track));
+        for method in ["DELETE", "PATCH"] {
+            let request = Request::builder()
+                .method(method)
+                .uri("/1")
+                .header("Authorization", "Bearer anything")
+                .body(Body::empty())
+                .unwrap();
+
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
         }
     }
 }
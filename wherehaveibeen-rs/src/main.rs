@@ -1,3 +1,4 @@
+mod cli;
 mod database;
 mod files;
 mod handlers;
@@ -5,130 +6,117 @@ mod model;
 mod routes;
 mod utils;
 
-use std::collections::HashMap;
-use std::path::Path;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::process::exit;
-use std::time::Instant;
 
-use axum::http::header::CONTENT_TYPE;
-use axum::http::Method;
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
+use axum::http::{HeaderValue, Method};
 use axum::Router;
-use database::heatmap::HeatmapDatabase;
-use database::tracks::TracksDatabase;
-use files::files::get_track_information;
-use files::gz::decompress_all_gz_files;
-use model::coordinate::{Coordinate, StringifiedCoordinate};
-use model::track::TrackInformation;
-use tower_http::cors::{Any, CorsLayer};
-use utils::{
-    cache_utils::save_cached_coordinates,
-    environment::{get_cache_directory, get_tracks_directory},
-    file_utils::{create_folder, get_valid_gps_files},
-};
-
-fn add_coordinates_to_heatmap(
-    heatmap: &mut HashMap<StringifiedCoordinate, i32>,
-    coordinates: &Vec<Coordinate>,
-) {
-    // First reduce the number of points that need to be inserted in the database by counting what
-    // is already in memory
-    for coordinate in coordinates {
-        // Round the coordinate to minimize points (Lose approx 11m of precision), Usually it would
-        // have 6 decimals but is now reduced to 5.
-        let number_of_decimals: usize = 5;
-        let rounded_coordinate = StringifiedCoordinate::new(
-            format!("{:.1$}", coordinate.latitude, number_of_decimals),
-            format!("{:.1$}", coordinate.longitude, number_of_decimals),
-        );
-        *heatmap.entry(rounded_coordinate).or_insert(0) += 1;
+use clap::Parser;
+use cli::{Cli, Command, IngestArgs, ServeArgs};
+use database::migrations::initialize_database;
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use utils::config::{get_config, initialize_config, Config};
+
+fn get_allowed_origins() -> AllowOrigin {
+    let config = get_config();
+    if config.allows_any_origin() {
+        return Any.into();
     }
-}
-
-fn initialize_data() {
-    let start = Instant::now();
-
-    let tracks_db = TracksDatabase::new().unwrap();
-    let mut heatmap_db = HeatmapDatabase::new().unwrap();
-
-    tracks_db.initialize_table().unwrap();
-    heatmap_db.initialize_table().unwrap();
-
-    // Get what is already stored in the database to avoid processing again the same files
-    // that have already been processed
-    let processed_files = tracks_db.get_all_filenames();
-
-    // Create the folder where the simplified gpx tracks will be stored
-    let cache_directory = get_cache_directory();
-    let cache_path = Path::new(&cache_directory);
-    create_folder(&cache_path).unwrap();
-
-    let tracks_directory = get_tracks_directory();
-    let path = Path::new(&tracks_directory);
-
-    // Sometimes the .fit tracks are stored as .fit.gz
-    decompress_all_gz_files(path).unwrap();
 
-    let files = get_valid_gps_files(path).unwrap();
-    let mut heatmap: HashMap<StringifiedCoordinate, i32> = HashMap::new();
-    for filename in files {
-        // Do not reprocess data already stored in the database for performance
-        // on the second startup
-        if processed_files.contains(&filename) {
-            continue;
-        }
-        let file_path = path.join(&filename);
+    // The origins have been validated when the configuration was loaded
+    let origins: Vec<HeaderValue> = config
+        .server
+        .cors_origins
+        .iter()
+        .filter_map(|origin| HeaderValue::from_str(origin).ok())
+        .collect();
+    AllowOrigin::list(origins)
+}
 
-        let track = get_track_information(file_path.as_path());
-        if let Ok((track_information, coordinates)) = track {
-            tracks_db
-                .insert_new_file(&filename, track_information, false)
-                .unwrap();
-            save_cached_coordinates(cache_path, &filename, &coordinates).unwrap();
-            add_coordinates_to_heatmap(&mut heatmap, &coordinates);
-        } else if let Err(e) = track {
-            eprintln!("No track information found for {}", filename);
-            eprintln!("Error: {}", e);
-            // The file is still inserted to prevent duplicated analysis on the next restart
-            tracks_db
-                .insert_new_file(&filename, TrackInformation::create_empty_track(), true)
-                .unwrap();
-        }
-    }
+async fn serve(args: ServeArgs) -> Result<(), String> {
+    println!("App is starting...");
 
-    println!("Saving heatmap...");
-    if let Err(err) = heatmap_db.update_heatmap(&mut heatmap) {
-        eprintln!("Error saving heatmap in the database: {}", err);
-        exit(1)
+    if !args.no_ingest {
+        cli::ingest(IngestArgs { directory: None })?;
     }
 
-    println!("Creating indices...");
-    heatmap_db.create_table_indices().unwrap();
-    tracks_db.create_table_indices().unwrap();
-
-    // Release connection
-    tracks_db.conn.close().unwrap();
-    heatmap_db.conn.close().unwrap();
+    let cors = CorsLayer::new()
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
+        .allow_origin(get_allowed_origins())
+        .allow_headers([CONTENT_TYPE, AUTHORIZATION]);
 
-    println!("Initialization took: {:?}", start.elapsed());
+    let app = Router::new()
+        .nest("/tracks", routes::tracks::router())
+        .nest("/heatmap", routes::heatmap::router())
+        .nest("/stats", routes::stats::router())
+        .nest("/activity-types", routes::activity_types::router())
+        .nest("/admin", routes::admin::router())
+        .merge(routes::layers::router())
+        .merge(routes::openapi::router())
+        .layer(cors)
+        // Compressed with gzip or brotli when the client accepts it
+        .layer(CompressionLayer::new());
+
+    let config = get_config();
+    let address = SocketAddr::new(
+        args.bind.unwrap_or(config.server.bind),
+        args.port.unwrap_or(config.server.port),
+    );
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .map_err(|e| format!("Can not listen on {}: {}", address, e))?;
+    println!("Listening on {}", address);
+    axum::serve(listener, app).await.map_err(|e| e.to_string())
 }
 
 #[tokio::main]
 async fn main() {
-    println!("App is starting...");
-
-    initialize_data();
+    let cli = Cli::parse();
+
+    let config_file = cli
+        .config
+        .or_else(|| std::env::var_os("CONFIG_FILE").map(PathBuf::from));
+    match Config::load(config_file.as_deref()) {
+        Ok(config) => initialize_config(config),
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            exit(1)
+        }
+    }
 
-    let cors = CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST])
-        .allow_origin(Any)
-        .allow_headers([CONTENT_TYPE]);
+    // Every command needs the tables with the latest schema
+    if let Err(e) = initialize_database() {
+        eprintln!("Error initializing the database: {}", e);
+        exit(1)
+    }
 
-    let app = Router::new()
-        .nest("/tracks", routes::tracks::router())
-        .nest("/heatmap", routes::heatmap::router())
-        .layer(cors);
+    let command = cli.command.unwrap_or(Command::Serve(ServeArgs::default()));
+    // The points of the heatmap must be rounded like the ones that will be added
+    if !matches!(command, Command::RebuildHeatmap) {
+        if let Err(e) = cli::check_heatmap_decimals() {
+            eprintln!("Error updating the heatmap: {}", e);
+            exit(1)
+        }
+    }
 
-    // run our app with hyper, listening globally on port 3000
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let result = match command {
+        Command::Serve(args) => serve(args).await,
+        Command::Ingest(args) => cli::ingest(args),
+        Command::RebuildHeatmap => cli::rebuild_heatmap(),
+        Command::Stats(args) => cli::stats(args),
+        Command::List(args) => cli::list(args),
+        Command::Export(args) => cli::export(args),
+        Command::Verify => cli::verify(),
+        Command::Classify => cli::classify(),
+        Command::CorrectElevations => cli::correct_elevations(),
+        Command::ImportLayers => cli::import_layers(),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        exit(1)
+    }
 }
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::utils::api_utils::Validate;
+
+const MAX_ACTIVITY_TYPE_LENGTH: usize = 50;
+
+pub fn validate_activity_type(field: &str, activity_type: &str) -> Result<(), String> {
+    if activity_type.trim().is_empty() {
+        return Err(format!("{} can not be empty", field));
+    }
+    if activity_type.chars().count() > MAX_ACTIVITY_TYPE_LENGTH {
+        return Err(format!(
+            "{} can not be longer than {} characters",
+            field, MAX_ACTIVITY_TYPE_LENGTH
+        ));
+    }
+
+    Ok(())
+}
+
+/// Activity type known by the app. Filtering by a type includes all its subtypes.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityTypeEntry {
+    pub name: String,
+    // Category the type belongs to, e.g. `cycling` for `gravel`
+    pub parent: Option<String>,
+    pub track_count: i64,
+}
+
+/// Activity type found in the files that is stored as a different one
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityTypeMapping {
+    pub raw_type: String,
+    pub activity_type: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityTypeRegistry {
+    pub activity_types: Vec<ActivityTypeEntry>,
+    pub mappings: Vec<ActivityTypeMapping>,
+}
+
+/// Result of changing a mapping. The tracks whose activity type was set by hand are not changed.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Reclassification {
+    pub raw_type: String,
+    pub activity_type: String,
+    pub reclassified_tracks: usize,
+}
+
+/// Without a parent the activity type is a root category
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ActivityTypeUpdate {
+    pub parent: Option<String>,
+}
+
+impl Validate for ActivityTypeUpdate {
+    fn validate(&self) -> Result<(), String> {
+        match &self.parent {
+            Some(parent) => validate_activity_type("parent", parent),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ActivityTypeMappingUpdate {
+    pub activity_type: String,
+}
+
+impl Validate for ActivityTypeMappingUpdate {
+    fn validate(&self) -> Result<(), String> {
+        validate_activity_type("activityType", &self.activity_type)
+    }
+}
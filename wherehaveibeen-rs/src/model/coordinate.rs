@@ -1,15 +1,19 @@
 use serde::Serialize;
+use utoipa::ToSchema;
 
-#[derive(Serialize)]
+/// Point of a track. Short field names are used to reduce the size of the responses.
+#[derive(Debug, Serialize, ToSchema)]
 pub struct Coordinate {
-    #[serde(rename(serialize = "a"))]
-    pub latitude: f32,
-    #[serde(rename(serialize = "o"))]
-    pub longitude: f32,
+    /// Latitude in degrees
+    #[serde(rename = "a")]
+    pub latitude: f64,
+    /// Longitude in degrees
+    #[serde(rename = "o")]
+    pub longitude: f64,
 }
 
 impl Coordinate {
-    pub fn new(latitude: f32, longitude: f32) -> Coordinate {
+    pub fn new(latitude: f64, longitude: f64) -> Coordinate {
         Coordinate {
             latitude,
             longitude,
@@ -17,8 +21,7 @@ impl Coordinate {
     }
 }
 
-
-// This is a support class used to built the heatmap as f32 should not be used as hash keys
+// This is a support class used to built the heatmap as floats should not be used as hash keys
 #[derive(Serialize, Eq, PartialEq, Hash, Debug)]
 pub struct StringifiedCoordinate {
     #[serde(rename(serialize = "a"))]
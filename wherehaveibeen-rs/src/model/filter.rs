@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Bounds {
+    pub north_west_latitude: f64,
+    pub north_west_longitude: f64,
+    pub south_east_latitude: f64,
+    pub south_east_longitude: f64,
+}
+
+// Longitudes out of range come from maps that can be panned around the world several times
+fn wrap_longitude(longitude: f64) -> f64 {
+    if (-180.0..=180.0).contains(&longitude) {
+        longitude
+    } else {
+        (longitude + 180.0).rem_euclid(360.0) - 180.0
+    }
+}
+
+impl Bounds {
+    /// Same area with the longitudes wrapped into [-180, 180] and the latitudes in order. The west
+    /// longitude is greater than the east one when the area crosses the antimeridian.
+    pub fn normalized(&self) -> Bounds {
+        let (west, east) = if self.south_east_longitude - self.north_west_longitude >= 360.0 {
+            (-180.0, 180.0)
+        } else {
+            (
+                wrap_longitude(self.north_west_longitude),
+                wrap_longitude(self.south_east_longitude),
+            )
+        };
+
+        Bounds {
+            north_west_latitude: self.north_west_latitude.max(self.south_east_latitude),
+            north_west_longitude: west,
+            south_east_latitude: self.north_west_latitude.min(self.south_east_latitude),
+            south_east_longitude: east,
+        }
+    }
+
+    /// West and east longitudes of the normalized area, split in two at the antimeridian when it
+    /// crosses it
+    pub fn longitude_ranges(&self) -> Vec<(f64, f64)> {
+        let west = self.north_west_longitude;
+        let east = self.south_east_longitude;
+        if west > east {
+            vec![(west, 180.0), (-180.0, east)]
+        } else {
+            vec![(west, east)]
+        }
+    }
+}
+
+/// Optional conditions that a track must fulfill. Conditions that are not set are ignored.
+#[derive(Debug, Default)]
+pub struct TrackFilter {
+    pub bounds: Option<Bounds>,
+    pub activity_type: Option<String>,
+    // RFC 3339 dates or a prefix of them, e.g. `2023` or `2023-05-01`
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    // Meters
+    pub min_distance: Option<f64>,
+    pub max_distance: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, ToSchema, clap::ValueEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum TrackSortField {
+    #[default]
+    Date,
+    Distance,
+    ElapsedTime,
+    MovingTime,
+    ElevationGain,
+    Filename,
+    ActivityType,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, ToSchema, clap::ValueEnum)]
+pub enum SortOrder {
+    #[serde(rename = "asc")]
+    #[value(name = "asc")]
+    Ascending,
+    #[default]
+    #[serde(rename = "desc")]
+    #[value(name = "desc")]
+    Descending,
+}
+
+/// Position after the last track of a page. It is sent to the client as an opaque hex string
+/// so that it does not need to be escaped in the URL.
+#[derive(Debug, PartialEq)]
+pub struct TrackCursor {
+    pub sort_value: String,
+    pub id: i64,
+}
+
+impl TrackCursor {
+    pub fn new(sort_value: String, id: i64) -> Self {
+        TrackCursor { sort_value, id }
+    }
+
+    pub fn encode(&self) -> String {
+        format!("{}|{}", self.sort_value, self.id)
+            .bytes()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let bytes = cursor
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| {
+                let pair = std::str::from_utf8(pair)
+                    .ok()
+                    .filter(|pair| pair.len() == 2)?;
+                u8::from_str_radix(pair, 16).ok()
+            })
+            .collect::<Option<Vec<u8>>>()?;
+        let decoded = String::from_utf8(bytes).ok()?;
+        let (sort_value, id) = decoded.rsplit_once('|')?;
+
+        Some(TrackCursor::new(sort_value.to_string(), id.parse().ok()?))
+    }
+}
+
+#[derive(Debug)]
+pub struct TrackListOptions {
+    pub sort_by: TrackSortField,
+    pub order: SortOrder,
+    pub limit: usize,
+    pub cursor: Option<TrackCursor>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = TrackCursor::new("2023-05-01T07:00:00+00:00".to_string(), 42);
+        let encoded = cursor.encode();
+        assert!(encoded.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(TrackCursor::decode(&encoded), Some(cursor));
+    }
+
+    #[test]
+    fn test_invalid_cursor() {
+        assert_eq!(TrackCursor::decode("abc"), None);
+        assert_eq!(TrackCursor::decode("zz"), None);
+        // "value" without an id
+        assert_eq!(TrackCursor::decode("76616c7565"), None);
+    }
+}
@@ -0,0 +1,119 @@
+use serde::Serialize;
+
+use super::{
+    coordinate::Coordinate,
+    heatmap::HeatmapCoordinate,
+    layer::{Route, RouteSummary, Waypoint},
+    track::TrackSummary,
+};
+
+// GeoJSON (RFC 7946) uses the longitude first
+fn to_position(latitude: f64, longitude: f64) -> [f64; 2] {
+    [longitude, latitude]
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
+pub enum Geometry {
+    Point { coordinates: [f64; 2] },
+    LineString { coordinates: Vec<[f64; 2]> },
+}
+
+impl Geometry {
+    pub fn line_string(coordinates: &[Coordinate]) -> Self {
+        Geometry::LineString {
+            coordinates: coordinates
+                .iter()
+                .map(|coordinate| to_position(coordinate.latitude, coordinate.longitude))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename = "Feature")]
+pub struct Feature<P: Serialize> {
+    pub geometry: Geometry,
+    pub properties: P,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename = "FeatureCollection")]
+pub struct FeatureCollection<P: Serialize> {
+    pub features: Vec<Feature<P>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeatmapProperties {
+    pub frequency: i64,
+}
+
+impl Feature<TrackSummary> {
+    pub fn from_track(summary: TrackSummary, coordinates: &[Coordinate]) -> Self {
+        Feature {
+            geometry: Geometry::line_string(coordinates),
+            properties: summary,
+        }
+    }
+}
+
+impl From<Route> for Feature<RouteSummary> {
+    fn from(route: Route) -> Self {
+        Feature {
+            geometry: Geometry::line_string(&route.coordinates),
+            properties: route.summary,
+        }
+    }
+}
+
+impl From<Waypoint> for Feature<Waypoint> {
+    fn from(waypoint: Waypoint) -> Self {
+        Feature {
+            geometry: Geometry::Point {
+                coordinates: to_position(waypoint.latitude, waypoint.longitude),
+            },
+            properties: waypoint,
+        }
+    }
+}
+
+impl From<Vec<HeatmapCoordinate>> for FeatureCollection<HeatmapProperties> {
+    fn from(heatmap: Vec<HeatmapCoordinate>) -> Self {
+        let features = heatmap
+            .into_iter()
+            .map(|point| Feature {
+                geometry: Geometry::Point {
+                    coordinates: to_position(point.latitude, point.longitude),
+                },
+                properties: HeatmapProperties {
+                    frequency: point.frequency,
+                },
+            })
+            .collect();
+
+        FeatureCollection { features }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heatmap_feature_collection() {
+        let heatmap = vec![HeatmapCoordinate::new(47.5, 11.1, 3)];
+        let collection = FeatureCollection::from(heatmap);
+
+        assert_eq!(
+            serde_json::to_value(collection).unwrap(),
+            serde_json::json!({
+                "type": "FeatureCollection",
+                "features": [{
+                    "type": "Feature",
+                    "geometry": { "type": "Point", "coordinates": [11.1, 47.5] },
+                    "properties": { "frequency": 3 }
+                }]
+            })
+        );
+    }
+}
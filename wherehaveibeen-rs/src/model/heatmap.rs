@@ -1,18 +1,37 @@
-use serde::Serialize;
+use std::fmt::Display;
 
-#[derive(Debug, Serialize)]
+use serde::{Serialize, Serializer};
+use utoipa::ToSchema;
+
+// The default JSON format sends the numbers as strings as it has always done
+fn serialize_as_string<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Display,
+{
+    serializer.collect_str(value)
+}
+
+/// Point of the heatmap rounded to 5 decimals. Short field names are used to reduce the size of
+/// the responses.
+#[derive(Debug, Serialize, ToSchema)]
 
 pub struct HeatmapCoordinate {
-    // Type is not relevant here as it will be just forwarded to the client
-    #[serde(rename(serialize = "a"))]
-    pub latitude: String,
-    #[serde(rename(serialize = "o"))]
-    pub longitude: String,
-    #[serde(rename(serialize = "f"))]
-    pub frequency: String,
+    /// Latitude in degrees
+    #[serde(rename = "a", serialize_with = "serialize_as_string")]
+    #[schema(value_type = String)]
+    pub latitude: f64,
+    /// Longitude in degrees
+    #[serde(rename = "o", serialize_with = "serialize_as_string")]
+    #[schema(value_type = String)]
+    pub longitude: f64,
+    /// Number of track points that passed through this point
+    #[serde(rename = "f", serialize_with = "serialize_as_string")]
+    #[schema(value_type = String)]
+    pub frequency: i64,
 }
 impl HeatmapCoordinate {
-    pub fn new(latitude: String, longitude: String, frequency: String) -> Self {
+    pub fn new(latitude: f64, longitude: f64, frequency: i64) -> Self {
         HeatmapCoordinate {
             latitude,
             longitude,
@@ -20,3 +39,13 @@ impl HeatmapCoordinate {
         }
     }
 }
+
+/// Outcome of rebuilding the heatmap from the cached coordinates of the tracks
+#[derive(Debug, Default, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HeatmapRebuild {
+    pub tracks: usize,
+    pub points: usize,
+    // Tracks without cached coordinates. They are not part of the heatmap
+    pub missing_tracks: Vec<String>,
+}
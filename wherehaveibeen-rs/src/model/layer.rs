@@ -0,0 +1,77 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::coordinate::Coordinate;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteSummary {
+    pub id: i64,
+    // GPX file the route was found in
+    pub filename: String,
+    // Id of the file in the tracks. Files with only routes and waypoints are deleted with it.
+    pub file_id: Option<i64>,
+    pub name: Option<String>,
+    // As declared in the file, without applying the activity type mappings
+    #[serde(rename = "type")]
+    pub route_type: Option<String>,
+    // Meters
+    pub distance: f64,
+}
+
+/// Planned route of a GPX file. Unlike tracks they do not have time and are not part of the
+/// heatmap.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Route {
+    #[serde(flatten)]
+    pub summary: RouteSummary,
+    pub coordinates: Vec<Coordinate>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RouteList {
+    pub routes: Vec<Route>,
+}
+
+/// Point of interest of a GPX file, like a summit, a hut or a campsite
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Waypoint {
+    pub id: i64,
+    // GPX file the waypoint was found in
+    pub filename: String,
+    // Id of the file in the tracks. Files with only routes and waypoints are deleted with it.
+    pub file_id: Option<i64>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    // Name of the icon given by the application that created the file
+    pub symbol: Option<String>,
+    #[serde(rename = "type")]
+    pub waypoint_type: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+    // Meters
+    pub elevation: Option<f64>,
+    pub time: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WaypointList {
+    pub waypoints: Vec<Waypoint>,
+}
+
+/// Routes and waypoints of a GPX file. The ids are 0 until they are stored.
+#[derive(Debug, Default)]
+pub struct FileLayers {
+    pub routes: Vec<Route>,
+    pub waypoints: Vec<Waypoint>,
+}
+
+/// Outcome of importing the routes and waypoints of the GPX files that are already stored
+#[derive(Debug, Default)]
+pub struct LayerImportResult {
+    pub routes: usize,
+    pub waypoints: usize,
+    // Files that can not be read
+    pub failed_files: Vec<String>,
+}
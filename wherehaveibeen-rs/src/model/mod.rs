@@ -1,4 +1,11 @@
 pub mod coordinate;
+pub mod filter;
+pub mod geojson;
+pub mod query;
 pub mod track;
 pub mod trackpoint;
-pub mod heatmap;
\ No newline at end of file
+pub mod heatmap;
+pub mod stats;
+pub mod activity_type;
+pub mod stream;
+pub mod layer;
\ No newline at end of file
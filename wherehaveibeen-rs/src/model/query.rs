@@ -0,0 +1,418 @@
+use chrono::{DateTime, NaiveDate};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::utils::api_utils::Validate;
+
+use super::filter::{
+    Bounds, SortOrder, TrackCursor, TrackFilter, TrackListOptions, TrackSortField,
+};
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 1000;
+const DEFAULT_PROFILE_POINTS: usize = 500;
+const MAX_PROFILE_POINTS: usize = 5000;
+
+// Longitudes are wrapped and the latitudes sorted when the bounds are used
+fn validate_bounds(bounds: &Bounds) -> Result<(), String> {
+    let corners = [
+        bounds.north_west_latitude,
+        bounds.north_west_longitude,
+        bounds.south_east_latitude,
+        bounds.south_east_longitude,
+    ];
+
+    if corners.iter().any(|corner| !corner.is_finite()) {
+        return Err("The corners of the area must be numbers".to_string());
+    }
+    if [bounds.north_west_latitude, bounds.south_east_latitude]
+        .iter()
+        .any(|latitude| !(-90.0..=90.0).contains(latitude))
+    {
+        return Err("Latitudes must be between -90 and 90".to_string());
+    }
+
+    Ok(())
+}
+
+// Dates can be given with any precision: `2023`, `2023-05`, `2023-05-01` or a full RFC 3339 date
+fn is_valid_date_prefix(date: &str) -> bool {
+    match date.len() {
+        4 => date.chars().all(|c| c.is_ascii_digit()),
+        7 => NaiveDate::parse_from_str(&format!("{}-01", date), "%Y-%m-%d").is_ok(),
+        10 => NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok(),
+        _ => DateTime::parse_from_rfc3339(date).is_ok(),
+    }
+}
+
+/// Area of the map. All the corners are required.
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct AreaQuery {
+    pub north_west_latitude: f64,
+    pub north_west_longitude: f64,
+    pub south_east_latitude: f64,
+    pub south_east_longitude: f64,
+}
+
+impl AreaQuery {
+    // Corners as requested, before wrapping the longitudes
+    fn corners(&self) -> Bounds {
+        Bounds {
+            north_west_latitude: self.north_west_latitude,
+            north_west_longitude: self.north_west_longitude,
+            south_east_latitude: self.south_east_latitude,
+            south_east_longitude: self.south_east_longitude,
+        }
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        self.corners().normalized()
+    }
+}
+
+impl Validate for AreaQuery {
+    fn validate(&self) -> Result<(), String> {
+        validate_bounds(&self.corners())
+    }
+}
+
+/// Filters shared by all the endpoints returning several tracks
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct TrackFilterQuery {
+    /// The four corners must be provided together
+    pub north_west_latitude: Option<f64>,
+    pub north_west_longitude: Option<f64>,
+    pub south_east_latitude: Option<f64>,
+    pub south_east_longitude: Option<f64>,
+    /// An empty value means all the activity types
+    pub activity_type: Option<String>,
+    /// `YYYY`, `YYYY-MM`, `YYYY-MM-DD` or a full RFC 3339 date
+    pub start_date: Option<String>,
+    /// `YYYY`, `YYYY-MM`, `YYYY-MM-DD` or a full RFC 3339 date
+    pub end_date: Option<String>,
+    /// Meters
+    pub min_distance: Option<f64>,
+    /// Meters
+    pub max_distance: Option<f64>,
+}
+
+impl TrackFilterQuery {
+    // Corners as requested, before wrapping the longitudes
+    fn corners(&self) -> Option<Bounds> {
+        Some(Bounds {
+            north_west_latitude: self.north_west_latitude?,
+            north_west_longitude: self.north_west_longitude?,
+            south_east_latitude: self.south_east_latitude?,
+            south_east_longitude: self.south_east_longitude?,
+        })
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        self.corners().map(|corners| corners.normalized())
+    }
+
+    pub fn has_bounds(&self) -> bool {
+        self.bounds().is_some()
+    }
+
+    pub fn to_filter(&self) -> TrackFilter {
+        TrackFilter {
+            bounds: self.bounds(),
+            // An empty activity type means all of them
+            activity_type: self.activity_type.clone().filter(|value| !value.is_empty()),
+            start_date: self.start_date.clone(),
+            end_date: self.end_date.clone(),
+            min_distance: self.min_distance,
+            max_distance: self.max_distance,
+        }
+    }
+}
+
+impl Validate for TrackFilterQuery {
+    fn validate(&self) -> Result<(), String> {
+        let corners = [
+            self.north_west_latitude,
+            self.north_west_longitude,
+            self.south_east_latitude,
+            self.south_east_longitude,
+        ];
+        match self.corners() {
+            Some(corners) => validate_bounds(&corners)?,
+            None if corners.iter().any(Option::is_some) => {
+                return Err("All the corners of the area must be provided".to_string());
+            }
+            None => {}
+        }
+
+        for date in [&self.start_date, &self.end_date].into_iter().flatten() {
+            if !is_valid_date_prefix(date) {
+                return Err(format!("Invalid date: {}", date));
+            }
+        }
+
+        if let (Some(min_distance), Some(max_distance)) = (self.min_distance, self.max_distance) {
+            if min_distance > max_distance {
+                return Err("minDistance must not be greater than maxDistance".to_string());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct TrackListQuery {
+    #[serde(default)]
+    #[param(inline)]
+    pub sort_by: TrackSortField,
+    #[serde(default)]
+    #[param(inline)]
+    pub order: SortOrder,
+    /// Between 1 and 1000. 50 by default
+    pub limit: Option<usize>,
+    /// `nextCursor` of the previous page
+    pub cursor: Option<String>,
+}
+
+impl TrackListQuery {
+    pub fn to_options(&self) -> TrackListOptions {
+        TrackListOptions {
+            sort_by: self.sort_by,
+            order: self.order,
+            limit: self.limit.unwrap_or(DEFAULT_PAGE_SIZE),
+            cursor: self.cursor.as_deref().and_then(TrackCursor::decode),
+        }
+    }
+}
+
+impl Validate for TrackListQuery {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(limit) = self.limit {
+            if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+                return Err(format!("limit must be between 1 and {}", MAX_PAGE_SIZE));
+            }
+        }
+        if let Some(cursor) = &self.cursor {
+            if TrackCursor::decode(cursor).is_none() {
+                return Err("Invalid cursor".to_string());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseFormat {
+    #[default]
+    Json,
+    GeoJson,
+    // Compact encodings. Each one is only available on some endpoints
+    Polyline,
+    Binary,
+}
+
+impl ResponseFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResponseFormat::Json => "json",
+            ResponseFormat::GeoJson => "geojson",
+            ResponseFormat::Polyline => "polyline",
+            ResponseFormat::Binary => "binary",
+        }
+    }
+}
+
+/// Output format of the endpoints returning coordinates. When it is not set, the `Accept` header
+/// is used instead.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FormatQuery {
+    #[param(inline)]
+    pub format: Option<ResponseFormat>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, ToSchema, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Gpx,
+    Kml,
+    Zip,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Gpx => "application/gpx+xml",
+            ExportFormat::Kml => "application/vnd.google-earth.kml+xml",
+            ExportFormat::Zip => "application/zip",
+        }
+    }
+
+    pub fn filename(&self) -> &'static str {
+        match self {
+            ExportFormat::Gpx => "tracks.gpx",
+            ExportFormat::Kml => "tracks.kml",
+            ExportFormat::Zip => "tracks.zip",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// GPX and KML contain the simplified coordinates and ZIP the original files
+    #[param(inline)]
+    pub format: ExportFormat,
+}
+
+impl Validate for ExportQuery {
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProfileQuery {
+    /// Between 2 and 5000. 500 by default. Tracks with fewer points are returned as they are
+    pub points: Option<usize>,
+}
+
+impl ProfileQuery {
+    pub fn get_points(&self) -> usize {
+        self.points.unwrap_or(DEFAULT_PROFILE_POINTS)
+    }
+}
+
+impl Validate for ProfileQuery {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(points) = self.points {
+            if !(2..=MAX_PROFILE_POINTS).contains(&points) {
+                return Err(format!(
+                    "points must be between 2 and {}",
+                    MAX_PROFILE_POINTS
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_filter_query() -> TrackFilterQuery {
+        TrackFilterQuery {
+            north_west_latitude: None,
+            north_west_longitude: None,
+            south_east_latitude: None,
+            south_east_longitude: None,
+            activity_type: None,
+            start_date: None,
+            end_date: None,
+            min_distance: None,
+            max_distance: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_area() {
+        let mut area = AreaQuery {
+            north_west_latitude: 49.0,
+            north_west_longitude: 11.0,
+            south_east_latitude: 48.0,
+            south_east_longitude: 12.0,
+        };
+        assert!(area.validate().is_ok());
+
+        area.south_east_latitude = -100.0;
+        assert!(area.validate().is_err());
+
+        area.south_east_latitude = f64::NAN;
+        assert!(area.validate().is_err());
+    }
+
+    #[test]
+    fn test_wrapped_area() {
+        // The map has been panned once around the world to the east
+        let area = AreaQuery {
+            north_west_latitude: 48.0,
+            north_west_longitude: 371.0,
+            south_east_latitude: 49.0,
+            south_east_longitude: 372.0,
+        };
+        assert!(area.validate().is_ok());
+
+        let bounds = area.bounds();
+        assert_eq!(bounds.north_west_latitude, 49.0);
+        assert_eq!(bounds.south_east_latitude, 48.0);
+        assert_eq!(bounds.longitude_ranges(), vec![(11.0, 12.0)]);
+
+        let area = AreaQuery {
+            north_west_longitude: -500.0,
+            south_east_longitude: 500.0,
+            ..area
+        };
+        assert_eq!(area.bounds().longitude_ranges(), vec![(-180.0, 180.0)]);
+    }
+
+    #[test]
+    fn test_area_across_antimeridian() {
+        let area = AreaQuery {
+            north_west_latitude: -16.0,
+            north_west_longitude: 178.0,
+            south_east_latitude: -18.0,
+            south_east_longitude: 182.0,
+        };
+        assert!(area.validate().is_ok());
+        assert_eq!(
+            area.bounds().longitude_ranges(),
+            vec![(178.0, 180.0), (-180.0, -178.0)]
+        );
+
+        let mut query = create_filter_query();
+        query.north_west_latitude = Some(-16.0);
+        query.north_west_longitude = Some(178.0);
+        query.south_east_latitude = Some(-18.0);
+        query.south_east_longitude = Some(-178.0);
+        assert!(query.validate().is_ok());
+        let bounds = query.to_filter().bounds.unwrap();
+        assert_eq!(
+            bounds.longitude_ranges(),
+            vec![(178.0, 180.0), (-180.0, -178.0)]
+        );
+    }
+
+    #[test]
+    fn test_validate_partial_bounds() {
+        let mut query = create_filter_query();
+        assert!(query.validate().is_ok());
+
+        query.north_west_latitude = Some(49.0);
+        assert!(query.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_dates() {
+        let mut query = create_filter_query();
+        for date in ["2023", "2023-05", "2023-05-01", "2023-05-01T07:00:00+02:00"] {
+            query.start_date = Some(date.to_string());
+            assert!(query.validate().is_ok(), "{}", date);
+        }
+
+        for date in ["23", "2023-13", "yesterday"] {
+            query.start_date = Some(date.to_string());
+            assert!(query.validate().is_err(), "{}", date);
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StatisticsGroup {
+    // Year, month, ISO week or activity type depending on the grouping
+    pub key: String,
+    pub count: i64,
+    // Meters
+    pub distance: f64,
+    // Seconds
+    pub elapsed_time: f64,
+    pub moving_time: f64,
+    // Meters
+    pub elevation_gain: f64,
+    // Tracks without time are not included in the times. Divide this distance by the moving time
+    // to get the average speed.
+    pub timed_distance: f64,
+    pub untimed_count: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Statistics {
+    pub total: StatisticsGroup,
+    pub by_year: Vec<StatisticsGroup>,
+    pub by_month: Vec<StatisticsGroup>,
+    pub by_week: Vec<StatisticsGroup>,
+    pub by_activity_type: Vec<StatisticsGroup>,
+}
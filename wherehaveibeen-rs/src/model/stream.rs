@@ -0,0 +1,58 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// One value per point. `None` when no point has a value
+pub type Channel = Option<Vec<Option<f64>>>;
+
+/// Values recorded at each point of a track. All the channels have one value per point and the
+/// channels that the file does not have are null.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackStreams {
+    /// Meters from the start
+    pub distance: Vec<f64>,
+    /// Seconds from the first point with time
+    #[schema(value_type = Option<Vec<Option<f64>>>)]
+    pub time: Channel,
+    /// Meters
+    #[schema(value_type = Option<Vec<Option<f64>>>)]
+    pub elevation: Channel,
+    /// Beats per minute
+    #[schema(value_type = Option<Vec<Option<f64>>>)]
+    pub heart_rate: Channel,
+    /// Revolutions or strides per minute
+    #[schema(value_type = Option<Vec<Option<f64>>>)]
+    pub cadence: Channel,
+    /// Watts
+    #[schema(value_type = Option<Vec<Option<f64>>>)]
+    pub power: Channel,
+    /// Meters per second as measured by the device
+    #[schema(value_type = Option<Vec<Option<f64>>>)]
+    pub speed: Channel,
+    /// Degrees Celsius
+    #[schema(value_type = Option<Vec<Option<f64>>>)]
+    pub temperature: Channel,
+}
+
+/// Values of the track at one point of the profile. The speed and the grade are measured from the
+/// previous sample, or to the next one for the first sample.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileSample {
+    /// Meters from the start
+    pub distance: f64,
+    /// Meters
+    pub elevation: Option<f64>,
+    /// Meters per second
+    pub speed: Option<f64>,
+    /// Percentage
+    pub grade: Option<f64>,
+    /// Seconds from the first point with time
+    pub time: Option<f64>,
+}
+
+/// Samples of the track at equal distances
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrackProfile {
+    pub samples: Vec<ProfileSample>,
+}
@@ -1,22 +1,106 @@
-use super::trackpoint::TrackPoint;
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize, Serializer};
+use utoipa::ToSchema;
+
+use crate::utils::api_utils::Validate;
+
+use super::{activity_type::validate_activity_type, filter::Bounds, trackpoint::TrackPoint};
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackMetrics {
+    // Meters
+    pub distance: f64,
+    // Seconds
+    pub elapsed_time: f64,
+    pub moving_time: f64,
+    // Meters per second
+    pub average_speed: f64,
+    pub max_speed: f64,
+    // Meters. Not all the files contain the altitude
+    pub elevation_gain: Option<f64>,
+    pub elevation_loss: Option<f64>,
+    pub min_altitude: Option<f64>,
+    pub max_altitude: Option<f64>,
+}
+
+// Confidence from which the guessed type is stored as the activity type of the tracks that do
+// not declare one
+const MIN_INFERENCE_CONFIDENCE: f64 = 0.6;
+
+// FIT sport of the change between the sports of a multisport activity
+const TRANSITION_SPORT: &str = "transition";
+const MULTISPORT: &str = "multisport";
+
+/// Part of an activity recorded with a single sport. Only FIT files have them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackSession {
+    pub sport: String,
+    // Not set when it is generic
+    pub sub_sport: Option<String>,
+    pub start_time: Option<String>,
+}
+
+/// Sport and sub-sport of the whole activity. The sessions with different sports are a
+/// multisport activity without sub-sport.
+pub fn get_sessions_sport(sessions: &[TrackSession]) -> Option<(&str, Option<&str>)> {
+    let mut sessions = sessions
+        .iter()
+        .filter(|session| session.sport != TRANSITION_SPORT);
+    let first = sessions.next()?;
+
+    let mut sub_sport = first.sub_sport.as_deref();
+    for session in sessions {
+        if session.sport != first.sport {
+            return Some((MULTISPORT, None));
+        }
+        if session.sub_sport.as_deref() != sub_sport {
+            sub_sport = None;
+        }
+    }
+
+    Some((&first.sport, sub_sport))
+}
+
+/// Activity type guessed from the recorded points
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityInference {
+    pub activity_type: String,
+    // Between 0 and 1
+    pub confidence: f64,
+}
+
+impl ActivityInference {
+    pub fn is_reliable(&self) -> bool {
+        self.confidence >= MIN_INFERENCE_CONFIDENCE
+    }
+}
 
 #[derive(Debug)]
 
 pub struct TrackInformation {
-    pub north_west_latitude: f32,
-    pub north_west_longitude: f32,
-    pub south_east_latitude: f32,
-    pub south_east_longitude: f32,
+    pub north_west_latitude: f64,
+    pub north_west_longitude: f64,
+    pub south_east_latitude: f64,
+    pub south_east_longitude: f64,
     pub date: String,
+    // As declared in the file. Empty if the file does not have one
     pub activity_type: String,
+    pub metrics: TrackMetrics,
+    pub inferred_activity_type: Option<ActivityInference>,
+    pub sessions: Vec<TrackSession>,
+    // None of the points has time, so the date is the one of the file
+    pub untimed: bool,
 }
 
 impl TrackInformation {
     pub fn new(
-        north_west_latitude: f32,
-        north_west_longitude: f32,
-        south_east_latitude: f32,
-        south_east_longitude: f32,
+        north_west_latitude: f64,
+        north_west_longitude: f64,
+        south_east_latitude: f64,
+        south_east_longitude: f64,
         date: String,
         activity_type: String,
     ) -> Self {
@@ -27,6 +111,10 @@ impl TrackInformation {
             south_east_longitude,
             date,
             activity_type,
+            metrics: TrackMetrics::default(),
+            inferred_activity_type: None,
+            sessions: Vec::new(),
+            untimed: false,
         }
     }
 
@@ -38,19 +126,250 @@ impl TrackInformation {
             south_east_longitude: 0.0,
             date: "".to_string(),
             activity_type: "".to_string(),
+            metrics: TrackMetrics::default(),
+            inferred_activity_type: None,
+            sessions: Vec::new(),
+            untimed: false,
+        }
+    }
+
+    /// Activity type to store before applying the mappings. The guessed type is only used when
+    /// the file does not declare one.
+    pub fn get_stored_activity_type(&self) -> &str {
+        if !self.activity_type.is_empty() {
+            return &self.activity_type;
+        }
+        match &self.inferred_activity_type {
+            Some(inference) if inference.is_reliable() => &inference.activity_type,
+            _ => "other",
+        }
+    }
+}
+
+// Public identifier of a track. The filename is only informative
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrackReference {
+    pub id: i64,
+    pub filename: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrackReferenceList {
+    pub tracks: Vec<TrackReference>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackSummary {
+    pub id: i64,
+    pub filename: String,
+    pub date: String,
+    pub activity_type: String,
+    pub bounds: Bounds,
+    #[serde(flatten)]
+    pub metrics: TrackMetrics,
+    // Given by the user. Not set by default
+    pub name: Option<String>,
+    // Activity type found in the file before applying the mappings. Empty if it has none
+    pub raw_activity_type: String,
+    // Guessed from the speed, cadence and elevation of the points, whatever the file declares
+    pub inferred_activity_type: Option<String>,
+    // Between 0 and 1
+    pub inferred_activity_type_confidence: Option<f64>,
+    // Only known for FIT files. `multisport` when the sessions have different sports.
+    pub sport: Option<String>,
+    pub sub_sport: Option<String>,
+    pub sessions: Vec<TrackSession>,
+    // The points do not have time, so the times and speeds are 0 and the date is the one declared
+    // by the file or its modification date
+    pub untimed: bool,
+}
+
+impl TrackSummary {
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.filename)
+    }
+}
+
+const MAX_NAME_LENGTH: usize = 200;
+
+/// Fields of a track that can be corrected. The fields that are not provided are kept and an
+/// empty name removes it.
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TrackUpdate {
+    pub activity_type: Option<String>,
+    pub name: Option<String>,
+}
+
+impl Validate for TrackUpdate {
+    fn validate(&self) -> Result<(), String> {
+        if self.activity_type.is_none() && self.name.is_none() {
+            return Err("activityType or name must be provided".to_string());
         }
+        if let Some(activity_type) = &self.activity_type {
+            validate_activity_type("activityType", activity_type)?;
+        }
+        if let Some(name) = &self.name {
+            if name.chars().count() > MAX_NAME_LENGTH {
+                return Err(format!(
+                    "name can not be longer than {} characters",
+                    MAX_NAME_LENGTH
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackList {
+    pub tracks: Vec<TrackSummary>,
+    // Not set when there are no more tracks
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityTypeList {
+    pub activity_types: Vec<String>,
+}
+
+/// Coordinates of a track as a Google encoded polyline with a precision of 5 decimals
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EncodedTrack {
+    pub polyline: String,
+}
+
+/// Why an uploaded file was not stored
+#[derive(Debug, PartialEq)]
+pub enum UploadRejection {
+    // Id of the stored track with the same content
+    Duplicate(i64),
+    // A different file with the same name is already in the tracks directory
+    Exists,
+    Unparseable(String),
+    // The file could not be written or the database failed
+    NotStored(String),
+}
+
+impl Display for UploadRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UploadRejection::Duplicate(id) => write!(f, "Duplicate of the track {}", id),
+            UploadRejection::Exists => {
+                write!(f, "A different file with the same name already exists")
+            }
+            UploadRejection::Unparseable(error) => write!(f, "Invalid track: {}", error),
+            UploadRejection::NotStored(error) => {
+                write!(f, "The file could not be stored: {}", error)
+            }
+        }
+    }
+}
+
+// The reason is sent to the client as a message
+impl Serialize for UploadRejection {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
     }
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RejectedFile {
+    pub filename: String,
+    #[schema(value_type = String)]
+    pub reason: UploadRejection,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadResult {
+    pub tracks: Vec<TrackSummary>,
+    pub rejected_files: Vec<RejectedFile>,
+}
+
+/// Outcome of processing the files of the tracks directory
+#[derive(Debug, Default)]
+pub struct IngestResult {
+    pub new_tracks: usize,
+    // Files that could not be parsed. They are stored as empty tracks
+    pub invalid_files: usize,
+    pub new_routes: usize,
+    pub new_waypoints: usize,
+}
+
+/// Outcome of guessing the activity type of the tracks stored before it was done on ingestion
+#[derive(Debug, Default)]
+pub struct ClassificationResult {
+    pub classified_tracks: usize,
+    // Files that can not be read or do not have enough timed points
+    pub unclassified_files: Vec<String>,
+}
+
+/// Outcome of computing again the elevations of the stored tracks from the DEM tiles
+#[derive(Debug, Default)]
+pub struct ElevationCorrectionResult {
+    pub corrected_tracks: usize,
+    // Tracks without points to correct or outside of the tiles
+    pub unchanged_tracks: usize,
+    // Files that can not be read
+    pub failed_files: Vec<String>,
+}
+
 pub struct TrackFile {
     pub track_points: Vec<TrackPoint>,
-    pub activity_type: String,
+    // `None` if the file does not declare it
+    pub activity_type: Option<String>,
+    pub sessions: Vec<TrackSession>,
+    // Date of the whole file. Only used when the points do not have time
+    pub date: Option<String>,
 }
 impl TrackFile {
-    pub fn new(track_points: Vec<TrackPoint>, activity_type: String) -> Self {
+    pub fn new(track_points: Vec<TrackPoint>, activity_type: Option<String>) -> Self {
         TrackFile {
             track_points,
             activity_type,
+            sessions: Vec::new(),
+            date: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_session(sport: &str, sub_sport: Option<&str>) -> TrackSession {
+        TrackSession {
+            sport: sport.to_string(),
+            sub_sport: sub_sport.map(str::to_string),
+            start_time: None,
         }
     }
+
+    #[test]
+    fn test_get_sessions_sport() {
+        assert_eq!(get_sessions_sport(&[]), None);
+
+        let sessions = [
+            create_session("cycling", Some("gravel_cycling")),
+            create_session("cycling", Some("gravel_cycling")),
+        ];
+        assert_eq!(
+            get_sessions_sport(&sessions),
+            Some(("cycling", Some("gravel_cycling")))
+        );
+
+        let sessions = [
+            create_session("swimming", Some("open_water")),
+            create_session("transition", None),
+            create_session("cycling", Some("road")),
+            create_session("transition", None),
+            create_session("running", None),
+        ];
+        assert_eq!(get_sessions_sport(&sessions), Some(("multisport", None)));
+    }
 }
@@ -1,18 +1,34 @@
 #[derive(Debug)]
 
 pub struct TrackPoint {
-    pub latitude: f32,
-    pub longitude: f32,
-    pub elevation: f32,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub elevation: f64,
     pub time: String,
+    // Sensor values. Only some devices record them
+    // Beats per minute
+    pub heart_rate: Option<f64>,
+    // Revolutions or strides per minute
+    pub cadence: Option<f64>,
+    // Watts
+    pub power: Option<f64>,
+    // Meters per second as measured by the device
+    pub speed: Option<f64>,
+    // Degrees Celsius
+    pub temperature: Option<f64>,
 }
 impl TrackPoint {
-    pub fn new(latitude: f32, longitude: f32, elevation: f32, time: String) -> Self {
+    pub fn new(latitude: f64, longitude: f64, elevation: f64, time: String) -> Self {
         TrackPoint {
             latitude,
             longitude,
             elevation,
             time,
+            heart_rate: None,
+            cadence: None,
+            power: None,
+            speed: None,
+            temperature: None,
         }
     }
 }
@@ -0,0 +1,15 @@
+use axum::{
+    routing::{get, put},
+    Router,
+};
+use crate::handlers::activity_types::*;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/", get(get_activity_type_registry))
+        .route("/:name", put(put_activity_type).delete(delete_activity_type))
+        .route(
+            "/mappings/:raw_type",
+            put(put_activity_type_mapping).delete(delete_activity_type_mapping),
+        )
+}
\ No newline at end of file
@@ -0,0 +1,10 @@
+use axum::{
+    routing::post,
+    Router,
+};
+use crate::handlers::admin::*;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/rebuild-heatmap", post(post_rebuild_heatmap))
+}
\ No newline at end of file
@@ -0,0 +1,12 @@
+use axum::{
+    routing::{delete, get},
+    Router,
+};
+use crate::handlers::layers::*;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/routes", get(get_routes))
+        .route("/waypoints", get(get_waypoints))
+        .route("/layers/:id", delete(delete_layer_file))
+}
\ No newline at end of file
@@ -1,2 +1,7 @@
 pub mod tracks;
-pub mod heatmap;
\ No newline at end of file
+pub mod heatmap;
+pub mod stats;
+pub mod openapi;
+pub mod admin;
+pub mod activity_types;
+pub mod layers;
\ No newline at end of file
@@ -0,0 +1,10 @@
+use axum::{
+    routing::get,
+    Router,
+};
+use crate::handlers::openapi::*;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/openapi.json", get(get_openapi_specification))
+}
@@ -0,0 +1,10 @@
+use axum::{
+    routing::get,
+    Router,
+};
+use crate::handlers::stats::*;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/", get(get_statistics))
+}
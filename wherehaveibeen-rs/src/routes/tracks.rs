@@ -1,14 +1,21 @@
 use axum::{
+    extract::DefaultBodyLimit,
     routing::get,
     Router,
 };
+use crate::files::upload::MAX_UPLOAD_SIZE;
 use crate::handlers::tracks::*;
 
 pub fn router() -> Router {
     Router::new()
-        .route("/", get(get_tracks))
+        .route("/", get(get_tracks).post(upload_tracks))
         .route("/activity-types", get(get_activity_types))
         .route("/filtered-tracks", get(get_filtered_tracks))
-        .route("/:filename", get(get_track))
-        .route("/coordinates/:filename", get(get_track_coordinates))
+        .route("/export", get(get_tracks_export))
+        .route("/:id", get(get_track).patch(update_track).delete(delete_track))
+        .route("/:id/summary", get(get_track_summary))
+        .route("/:id/streams", get(get_track_streams))
+        .route("/:id/profile", get(get_track_profile))
+        .route("/coordinates/:id", get(get_track_coordinates))
+        .layer(DefaultBodyLimit::max(MAX_UPLOAD_SIZE))
 }
\ No newline at end of file
@@ -1,23 +1,10 @@
-use std::collections::HashMap;
-
-use super::environment::get_activity_type_conversions;
-
+/// Activity type as found in the file. The mappings are applied when the track is stored.
 pub fn sanitize_activity_type(activity_type: &str) -> String {
-    let conversions_json = get_activity_type_conversions();
-    let conversions: HashMap<String, String> =
-        serde_json::from_str(&conversions_json).expect("Invalid JSON format in CONVERSIONS_JSON");
-
-    match conversions.get(activity_type) {
-        Some(conversion) => {
-            return conversion.clone();
-        }
-        None => {
-            if activity_type.is_empty() {
-                return "other".to_string();
-            }
-            return activity_type.to_string();
-        }
+    let activity_type = activity_type.trim();
+    if activity_type.is_empty() {
+        return "other".to_string();
     }
+    activity_type.to_string()
 }
 
 #[cfg(test)]
@@ -25,8 +12,8 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_conversion() {
-        let result = sanitize_activity_type("StandUpPaddling");
-        assert_eq!(result, "Stand Up Paddling".to_string());
+    fn test_sanitize_activity_type() {
+        assert_eq!(sanitize_activity_type("StandUpPaddling"), "StandUpPaddling");
+        assert_eq!(sanitize_activity_type(" "), "other".to_string());
     }
 }
@@ -1,5 +1,11 @@
-use axum::{http::StatusCode, response::IntoResponse, Json};
-use serde_json::json;
+use axum::{
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+
+pub const GEOJSON_CONTENT_TYPE: &str = "application/geo+json";
+pub const BINARY_CONTENT_TYPE: &str = "application/octet-stream";
 
 pub fn json_ok<T>(body: T) -> impl IntoResponse
 where
@@ -8,11 +14,17 @@ where
     (StatusCode::OK, Json(body))
 }
 
-pub fn json_not_found(message: &str) -> impl IntoResponse {
-    (StatusCode::NOT_FOUND, Json(json!({ "message": message })))
+pub fn geojson_ok<T>(body: T) -> impl IntoResponse
+where
+    T: serde::Serialize,
+{
+    (
+        StatusCode::OK,
+        [(CONTENT_TYPE, GEOJSON_CONTENT_TYPE)],
+        Json(body),
+    )
 }
 
-// A helper function for a generic error response
-// pub fn json_error(status: StatusCode, message: &str) -> impl IntoResponse {
-//     (status, Json(json!({ "message": message })))
-// }
+pub fn binary_ok(body: Vec<u8>) -> impl IntoResponse {
+    (StatusCode::OK, [(CONTENT_TYPE, BINARY_CONTENT_TYPE)], body)
+}
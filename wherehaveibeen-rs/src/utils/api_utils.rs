@@ -1,11 +1,187 @@
-use std::{collections::HashMap, str::FromStr};
+use axum::{
+    async_trait,
+    extract::{FromRequest, FromRequestParts, Path, Query, Request},
+    http::{
+        header::{ACCEPT, AUTHORIZATION},
+        request::Parts,
+        HeaderMap,
+    },
+    Json,
+};
+use serde::de::DeserializeOwned;
 
-pub fn get_query_parameter<T>(params: &HashMap<String, String>, value: &str) -> T
+use crate::model::query::{FormatQuery, ResponseFormat};
+
+use super::api_error::ApiError;
+use super::api_response::GEOJSON_CONTENT_TYPE;
+use super::config::get_config;
+
+pub trait Validate {
+    fn validate(&self) -> Result<(), String>;
+}
+
+/// Query string parameters that are deserialized and validated before reaching the handler.
+/// Any problem is returned to the client as a bad request.
+pub struct ValidatedQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+        value.validate().map_err(ApiError::BadRequest)?;
+
+        Ok(ValidatedQuery(value))
+    }
+}
+
+/// JSON body that is deserialized and validated before reaching the handler.
+/// Any problem is returned to the client as a bad request.
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+        value.validate().map_err(ApiError::BadRequest)?;
+
+        Ok(ValidatedJson(value))
+    }
+}
+
+/// Same as the axum `Path` but with the error format of the rest of the API
+pub struct ApiPath<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ApiPath<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path(value) = Path::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+
+        Ok(ApiPath(value))
+    }
+}
+
+/// Format requested with the `format` query parameter or, if it is missing, the `Accept` header
+pub struct RequestedFormat(pub ResponseFormat);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestedFormat
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<FormatQuery>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+        if let Some(format) = query.format {
+            return Ok(RequestedFormat(format));
+        }
+
+        let accepts_geojson = parts
+            .headers
+            .get(ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains(GEOJSON_CONTENT_TYPE));
+        if accepts_geojson {
+            Ok(RequestedFormat(ResponseFormat::GeoJson))
+        } else {
+            Ok(RequestedFormat(ResponseFormat::Json))
+        }
+    }
+}
+
+// Every byte is compared so that the time does not tell how much of the token is right
+fn is_same_token(provided: &str, token: &str) -> bool {
+    provided.len() == token.len()
+        && provided
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+fn check_admin_token(token: Option<&str>, headers: &HeaderMap) -> Result<(), ApiError> {
+    let Some(token) = token else {
+        return Err(ApiError::Forbidden(
+            "The admin endpoints are disabled. Set admin.token to enable them".to_string(),
+        ));
+    };
+
+    let provided = headers
+        .get(AUTHORIZATION)
+        .and_then(|authorization| authorization.to_str().ok())
+        .and_then(|authorization| authorization.strip_prefix("Bearer "));
+    match provided {
+        Some(provided) if is_same_token(provided, token) => Ok(()),
+        _ => Err(ApiError::Unauthorized(
+            "A valid admin token is required".to_string(),
+        )),
+    }
+}
+
+/// Request authorized with the admin token of the configuration. The admin endpoints are
+/// disabled when there is no token.
+pub struct AdminAccess;
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AdminAccess
 where
-    T: FromStr + Default,
+    S: Send + Sync,
 {
-    return params
-        .get(value)
-        .and_then(|v| v.parse::<T>().ok())
-        .unwrap_or_default();
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        check_admin_token(get_config().admin.token.as_deref(), &parts.headers)?;
+
+        Ok(AdminAccess)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_admin_token() {
+        let mut headers = HeaderMap::new();
+        assert!(matches!(
+            check_admin_token(None, &headers),
+            Err(ApiError::Forbidden(_))
+        ));
+        assert!(matches!(
+            check_admin_token(Some("secret"), &headers),
+            Err(ApiError::Unauthorized(_))
+        ));
+
+        headers.insert(AUTHORIZATION, "Bearer secreT".parse().unwrap());
+        assert!(check_admin_token(Some("secret"), &headers).is_err());
+        headers.insert(AUTHORIZATION, "Bearer secret".parse().unwrap());
+        assert!(check_admin_token(Some("secret"), &headers).is_ok());
+        assert!(check_admin_token(None, &headers).is_err());
+    }
 }
@@ -1,28 +1,43 @@
 use std::path::Path;
 
-use crate::model::coordinate::Coordinate;
+use crate::model::{coordinate::Coordinate, trackpoint::TrackPoint};
 
-use super::file_utils::{read_file, save_to_file};
+use super::file_utils::save_to_file;
 
-pub fn save_cached_coordinates(
+fn format_value(value: Option<f64>) -> String {
+    value
+        .filter(|value| !value.is_nan())
+        .map(|value| value.to_string())
+        .unwrap_or_default()
+}
+
+/// Each line has the coordinates of a point followed by its elevation, time and sensor values,
+/// which are empty when they are unknown
+pub fn save_cached_track(
     path: &Path,
     filename: &str,
-    coordinates: &Vec<Coordinate>,
+    track_points: &[TrackPoint],
 ) -> Result<(), std::io::Error> {
     let mut content = String::new();
-    for coordinate in coordinates {
+    for point in track_points {
         content.push_str(&format!(
-            "{},{}\n",
-            coordinate.latitude, coordinate.longitude
+            "{},{},{},{},{},{},{},{},{}\n",
+            point.latitude,
+            point.longitude,
+            format_value(Some(point.elevation)),
+            point.time,
+            format_value(point.heart_rate),
+            format_value(point.cadence),
+            format_value(point.power),
+            format_value(point.speed),
+            format_value(point.temperature),
         ));
     }
 
     save_to_file(path.join(filename).as_path(), &content)
 }
 
-pub fn read_cached_coordinates(path: &Path) -> Result<Vec<Coordinate>, std::io::Error> {
-    let content = read_file(path)?;
-
+pub fn parse_cached_coordinates(content: &str) -> Vec<Coordinate> {
     let mut coordinates: Vec<Coordinate> = Vec::new();
     for line in content.lines() {
         let mut parts = line.split(',');
@@ -33,5 +48,53 @@ pub fn read_cached_coordinates(path: &Path) -> Result<Vec<Coordinate>, std::io::
         }
     }
 
-    Ok(coordinates)
+    coordinates
+}
+
+/// Points with all the cached values. Tracks cached by older versions only have coordinates.
+pub fn parse_cached_track_points(content: &str) -> Vec<TrackPoint> {
+    let mut track_points: Vec<TrackPoint> = Vec::new();
+    for line in content.lines() {
+        let mut parts = line.split(',');
+        let (Some(Ok(latitude)), Some(Ok(longitude))) =
+            (parts.next().map(str::parse), parts.next().map(str::parse))
+        else {
+            continue;
+        };
+        let elevation = parts.next().and_then(|part| part.parse().ok());
+        let time = parts.next().unwrap_or_default().to_string();
+
+        let mut point = TrackPoint::new(latitude, longitude, elevation.unwrap_or(f64::NAN), time);
+        let mut next_value = || parts.next().and_then(|part| part.parse().ok());
+        point.heart_rate = next_value();
+        point.cadence = next_value();
+        point.power = next_value();
+        point.speed = next_value();
+        point.temperature = next_value();
+        track_points.push(point);
+    }
+
+    track_points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cached_track_points() {
+        let content = "47.5,11.1,700.5,2024-03-01T08:00:00+00:00,120,,250,,18.5\n47.6,11.2\n";
+
+        let track_points = parse_cached_track_points(content);
+        assert_eq!(track_points.len(), 2);
+        assert_eq!(track_points[0].elevation, 700.5);
+        assert_eq!(track_points[0].time, "2024-03-01T08:00:00+00:00");
+        assert_eq!(track_points[0].heart_rate, Some(120.0));
+        assert_eq!(track_points[0].cadence, None);
+        assert_eq!(track_points[0].power, Some(250.0));
+        assert_eq!(track_points[0].temperature, Some(18.5));
+        assert!(track_points[1].elevation.is_nan());
+        assert_eq!(track_points[1].time, "");
+        assert_eq!(parse_cached_coordinates(content).len(), 2);
+    }
 }
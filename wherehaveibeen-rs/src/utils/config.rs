@@ -0,0 +1,395 @@
+use std::{
+    collections::HashMap,
+    env,
+    net::{IpAddr, Ipv4Addr},
+    path::Path,
+    str::FromStr,
+    sync::OnceLock,
+};
+
+use axum::http::HeaderValue;
+use serde::Deserialize;
+
+// Read when no other file is given and it exists in the working directory
+const DEFAULT_CONFIG_FILE: &str = "wherehaveibeen.toml";
+const MAX_HEATMAP_DECIMALS: usize = 5;
+const ACTIVITY_TYPE_CONVERSIONS: &[(&str, &str)] = &[
+    ("StandUpPaddling", "Stand Up Paddling"),
+    ("IceSkate", "Ice Skate"),
+    ("generic", "other"),
+    ("hiking", "walking"),
+    ("ebikeride", "cycling"),
+    ("Biking", "cycling"),
+    ("biking", "cycling"),
+    ("1", "cycling"),
+    ("Velomobile", "cycling"),
+];
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathsConfig {
+    // Original GPX, FIT and TCX files
+    pub tracks_directory: String,
+    // Simplified coordinates of each track
+    pub cache_directory: String,
+    pub database: String,
+}
+
+impl Default for PathsConfig {
+    fn default() -> Self {
+        PathsConfig {
+            tracks_directory: "tracks".to_string(),
+            cache_directory: ".cached_tracks".to_string(),
+            database: "tracks_database.db".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind: IpAddr,
+    pub port: u16,
+    // `*` allows any origin
+    pub cors_origins: Vec<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            port: 3000,
+            cors_origins: vec!["*".to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HeatmapConfig {
+    // The points of the tracks are rounded to this number of decimals. 5 decimals are about 1 m.
+    // The heatmap must be rebuilt after changing it.
+    pub decimals: usize,
+}
+
+impl Default for HeatmapConfig {
+    fn default() -> Self {
+        HeatmapConfig {
+            decimals: MAX_HEATMAP_DECIMALS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ElevationCorrection {
+    // Only the points without elevation
+    #[default]
+    Fill,
+    // Every point covered by a tile
+    Replace,
+}
+
+impl FromStr for ElevationCorrection {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "fill" => Ok(ElevationCorrection::Fill),
+            "replace" => Ok(ElevationCorrection::Replace),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ElevationConfig {
+    // SRTM `.hgt` tiles named after their south west corner, like `N47E011.hgt`. The elevations
+    // are not corrected when it is not set.
+    pub dem_directory: Option<String>,
+    pub correction: ElevationCorrection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminConfig {
+    // Sent as `Authorization: Bearer <token>` to the `/admin` endpoints and the ones that upload,
+    // change or delete data. They are disabled when it is not set.
+    pub token: Option<String>,
+}
+
+/// Settings read from a TOML file. Every value can be overridden with an environment variable.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub paths: PathsConfig,
+    pub server: ServerConfig,
+    pub heatmap: HeatmapConfig,
+    pub elevation: ElevationConfig,
+    pub admin: AdminConfig,
+    // Activity type found in the files and the one that is stored instead. They are only the
+    // initial mappings of a new database, afterwards they are managed with the API.
+    pub activity_types: HashMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            paths: PathsConfig::default(),
+            server: ServerConfig::default(),
+            heatmap: HeatmapConfig::default(),
+            elevation: ElevationConfig::default(),
+            admin: AdminConfig::default(),
+            activity_types: ACTIVITY_TYPE_CONVERSIONS
+                .iter()
+                .map(|(from, to)| (from.to_string(), to.to_string()))
+                .collect(),
+        }
+    }
+}
+
+fn parse_environment_variable<T: FromStr>(variable: &str) -> Result<Option<T>, String> {
+    match env::var(variable) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("Invalid value for {}: {}", variable, value)),
+        Err(_) => Ok(None),
+    }
+}
+
+impl Config {
+    fn parse(content: &str) -> Result<Config, String> {
+        toml::from_str(content).map_err(|e| e.to_string())
+    }
+
+    /// Read the given file, or the default one if it exists, and apply the environment variables
+    pub fn load(path: Option<&Path>) -> Result<Config, String> {
+        let default_path = Path::new(DEFAULT_CONFIG_FILE);
+        let path = path.or(default_path.exists().then_some(default_path));
+
+        let mut config = match path {
+            Some(path) => {
+                println!("Reading configuration from {}", path.display());
+                let content = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Can not read {}: {}", path.display(), e))?;
+                Config::parse(&content).map_err(|e| format!("{}: {}", path.display(), e))?
+            }
+            None => Config::default(),
+        };
+        config.apply_environment()?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    fn apply_environment(&mut self) -> Result<(), String> {
+        if let Some(value) = parse_environment_variable("TRACKS_DIRECTORY")? {
+            self.paths.tracks_directory = value;
+        }
+        if let Some(value) = parse_environment_variable("CACHE_DIRECTORY")? {
+            self.paths.cache_directory = value;
+        }
+        if let Some(value) = parse_environment_variable("DATABASE_PATH")? {
+            self.paths.database = value;
+        }
+        if let Some(value) = parse_environment_variable("SERVER_BIND")? {
+            self.server.bind = value;
+        }
+        if let Some(value) = parse_environment_variable("SERVER_PORT")? {
+            self.server.port = value;
+        }
+        if let Some(value) = parse_environment_variable::<String>("CORS_ORIGINS")? {
+            self.server.cors_origins = value
+                .split(',')
+                .map(|origin| origin.trim().to_string())
+                .collect();
+        }
+        if let Some(value) = parse_environment_variable("HEATMAP_DECIMALS")? {
+            self.heatmap.decimals = value;
+        }
+        if let Some(value) = parse_environment_variable("DEM_DIRECTORY")? {
+            self.elevation.dem_directory = Some(value);
+        }
+        if let Some(value) = parse_environment_variable("ELEVATION_CORRECTION")? {
+            self.elevation.correction = value;
+        }
+        if let Some(value) = parse_environment_variable("ADMIN_TOKEN")? {
+            self.admin.token = Some(value);
+        }
+        // Kept as JSON for the existing deployments
+        if let Ok(value) = env::var("ACTIVITY_TYPE_CONVERSIONS") {
+            self.activity_types = serde_json::from_str(&value)
+                .map_err(|e| format!("Invalid JSON in ACTIVITY_TYPE_CONVERSIONS: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let paths = [
+            ("paths.tracks_directory", &self.paths.tracks_directory),
+            ("paths.cache_directory", &self.paths.cache_directory),
+            ("paths.database", &self.paths.database),
+        ];
+        for (name, path) in paths {
+            if path.trim().is_empty() {
+                return Err(format!("{} can not be empty", name));
+            }
+        }
+        if self.paths.tracks_directory == self.paths.cache_directory {
+            return Err(
+                "paths.cache_directory must be different from paths.tracks_directory".to_string(),
+            );
+        }
+
+        if self.server.port == 0 {
+            return Err("server.port can not be 0".to_string());
+        }
+        if self.server.cors_origins.is_empty() {
+            return Err("server.cors_origins needs at least one origin or *".to_string());
+        }
+        for origin in &self.server.cors_origins {
+            let is_url = origin.starts_with("http://") || origin.starts_with("https://");
+            if origin != "*" && (!is_url || HeaderValue::from_str(origin).is_err()) {
+                return Err(format!("Invalid CORS origin: {}", origin));
+            }
+        }
+
+        if !(1..=MAX_HEATMAP_DECIMALS).contains(&self.heatmap.decimals) {
+            return Err(format!(
+                "heatmap.decimals must be between 1 and {}",
+                MAX_HEATMAP_DECIMALS
+            ));
+        }
+
+        if let Some(directory) = &self.elevation.dem_directory {
+            if !Path::new(directory).is_dir() {
+                return Err(format!(
+                    "elevation.dem_directory {} is not a directory",
+                    directory
+                ));
+            }
+        }
+
+        if self
+            .admin
+            .token
+            .as_ref()
+            .is_some_and(|token| token.trim().is_empty())
+        {
+            return Err("admin.token can not be empty".to_string());
+        }
+
+        if let Some((from, _)) = self
+            .activity_types
+            .iter()
+            .find(|(_, to)| to.trim().is_empty())
+        {
+            return Err(format!("The activity type {} is mapped to nothing", from));
+        }
+
+        Ok(())
+    }
+
+    pub fn allows_any_origin(&self) -> bool {
+        self.server.cors_origins.iter().any(|origin| origin == "*")
+    }
+}
+
+/// Must be called once on startup before the configuration is used
+pub fn initialize_config(config: Config) {
+    if CONFIG.set(config).is_err() {
+        eprintln!("The configuration has already been initialized");
+    }
+}
+
+/// Configuration given on startup or the default one
+pub fn get_config() -> &'static Config {
+    CONFIG.get_or_init(default_config)
+}
+
+#[cfg(not(test))]
+fn default_config() -> Config {
+    Config::default()
+}
+
+// The tests must not use the files of the working directory
+#[cfg(test)]
+fn default_config() -> Config {
+    let directory = std::env::temp_dir().join(format!("whib-test-{}", std::process::id()));
+    let path = |name: &str| directory.join(name).to_string_lossy().into_owned();
+    Config {
+        paths: PathsConfig {
+            tracks_directory: path("tracks"),
+            cache_directory: path("cache"),
+            database: format!("{}.db", directory.to_string_lossy()),
+        },
+        ..Config::default()
+    }
+}
+
+pub fn get_database_path() -> String {
+    get_config().paths.database.clone()
+}
+
+pub fn get_cache_directory() -> String {
+    get_config().paths.cache_directory.clone()
+}
+
+pub fn get_tracks_directory() -> String {
+    get_config().paths.tracks_directory.clone()
+}
+
+pub fn get_dem_directory() -> Option<String> {
+    get_config().elevation.dem_directory.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse(
+            r#"
+            [paths]
+            tracks_directory = "/data/tracks"
+
+            [server]
+            port = 8080
+            cors_origins = ["https://example.com"]
+
+            [activity_types]
+            gravel = "cycling"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.paths.tracks_directory, "/data/tracks");
+        assert_eq!(config.paths.database, "tracks_database.db");
+        assert_eq!(config.server.port, 8080);
+        assert!(!config.allows_any_origin());
+        assert_eq!(config.heatmap.decimals, 5);
+        assert_eq!(config.activity_types.len(), 1);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(Config::parse("[server]\nport = \"abc\"").is_err());
+        assert!(Config::parse("[paths]\nunknown = 1").is_err());
+
+        let config = Config::parse("[heatmap]\ndecimals = 7").unwrap();
+        assert!(config.validate().is_err());
+        let config = Config::parse("[server]\ncors_origins = [\"example.com\"]").unwrap();
+        assert!(config.validate().is_err());
+        assert!(Config::parse("[elevation]\ncorrection = \"smooth\"").is_err());
+        let config = Config::parse("[admin]\ntoken = \" \"").unwrap();
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use crate::model::{coordinate::Coordinate, heatmap::HeatmapCoordinate};
+
+// Same precision used to round the heatmap (approx 1m)
+const COORDINATE_FACTOR: f64 = 1e5;
+
+fn encode_polyline_value(value: i64, output: &mut String) {
+    let mut value = if value < 0 { !(value << 1) } else { value << 1 };
+    while value >= 0x20 {
+        output.push(char::from((0x20 | (value & 0x1f)) as u8 + 63));
+        value >>= 5;
+    }
+    output.push(char::from(value as u8 + 63));
+}
+
+/// Encode the coordinates with the Google encoded polyline algorithm and a precision of 5 decimals
+pub fn encode_polyline(coordinates: &[Coordinate]) -> String {
+    let mut output = String::new();
+    let (mut previous_latitude, mut previous_longitude) = (0, 0);
+
+    for coordinate in coordinates {
+        let latitude = (coordinate.latitude * COORDINATE_FACTOR).round() as i64;
+        let longitude = (coordinate.longitude * COORDINATE_FACTOR).round() as i64;
+        encode_polyline_value(latitude - previous_latitude, &mut output);
+        encode_polyline_value(longitude - previous_longitude, &mut output);
+        previous_latitude = latitude;
+        previous_longitude = longitude;
+    }
+
+    output
+}
+
+/// Pack the heatmap as consecutive 12 bytes little-endian records: latitude and longitude as
+/// `i32` multiplied by 100000 followed by the frequency as `u32`
+pub fn encode_heatmap(heatmap: &[HeatmapCoordinate]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(heatmap.len() * 12);
+
+    for point in heatmap {
+        let latitude = (point.latitude * COORDINATE_FACTOR).round() as i32;
+        let longitude = (point.longitude * COORDINATE_FACTOR).round() as i32;
+        let frequency = point.frequency.clamp(0, u32::MAX as i64) as u32;
+        output.extend_from_slice(&latitude.to_le_bytes());
+        output.extend_from_slice(&longitude.to_le_bytes());
+        output.extend_from_slice(&frequency.to_le_bytes());
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_polyline() {
+        // Example from the documentation of the algorithm
+        let coordinates = vec![
+            Coordinate::new(38.5, -120.2),
+            Coordinate::new(40.7, -120.95),
+            Coordinate::new(43.252, -126.453),
+        ];
+        assert_eq!(encode_polyline(&coordinates), "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+        assert_eq!(encode_polyline(&[]), "");
+    }
+
+    #[test]
+    fn test_encode_heatmap() {
+        let heatmap = vec![HeatmapCoordinate::new(47.50001, -11.1, 3)];
+        let encoded = encode_heatmap(&heatmap);
+
+        assert_eq!(encoded.len(), 12);
+        assert_eq!(
+            i32::from_le_bytes(encoded[0..4].try_into().unwrap()),
+            4750001
+        );
+        assert_eq!(
+            i32::from_le_bytes(encoded[4..8].try_into().unwrap()),
+            -1110000
+        );
+        assert_eq!(u32::from_le_bytes(encoded[8..12].try_into().unwrap()), 3);
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    io::{Error, ErrorKind},
+    path::{Component, Path, PathBuf},
+};
+
+/// Resolve a path relative to the given root making sure that the result is still inside of it.
+/// Symbolic links are followed, so a link pointing outside of the root is rejected as well.
+pub fn resolve_path_inside(root: &Path, relative_path: &str) -> Result<PathBuf, Error> {
+    let relative = Path::new(relative_path);
+    let is_plain_relative = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    if relative_path.is_empty() || !is_plain_relative {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            "The path is not a plain relative path",
+        ));
+    }
+
+    let canonical_root = root.canonicalize()?;
+    let canonical_path = canonical_root.join(relative).canonicalize()?;
+    if !canonical_path.starts_with(&canonical_root) || !canonical_path.is_file() {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            "The path is outside of the allowed directory",
+        ));
+    }
+
+    Ok(canonical_path)
+}
+
+pub fn get_content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+
+    match extension.as_deref() {
+        Some("gpx") => "application/gpx+xml",
+        Some("tcx") => "application/vnd.garmin.tcx+xml",
+        Some("fit") => "application/vnd.ant.fit",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_resolve_path_inside() {
+        let root =
+            std::env::temp_dir().join(format!("whib-file-access-test-{}", std::process::id()));
+        fs::create_dir_all(root.join("tracks")).unwrap();
+        fs::write(root.join("tracks").join("track.gpx"), "").unwrap();
+        fs::write(root.join("secret.txt"), "").unwrap();
+        let tracks = root.join("tracks");
+
+        let results = [
+            resolve_path_inside(&tracks, "track.gpx").is_ok(),
+            resolve_path_inside(&tracks, "missing.gpx").is_err(),
+            resolve_path_inside(&tracks, "../secret.txt").is_err(),
+            resolve_path_inside(&tracks, "/etc/passwd").is_err(),
+            resolve_path_inside(&tracks, "").is_err(),
+            resolve_path_inside(&root, "tracks").is_err(),
+        ];
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(results, [true; 6]);
+    }
+
+    #[test]
+    fn test_get_content_type() {
+        assert_eq!(get_content_type(Path::new("a.gpx")), "application/gpx+xml");
+        assert_eq!(
+            get_content_type(Path::new("a.FIT")),
+            "application/vnd.ant.fit"
+        );
+        assert_eq!(
+            get_content_type(Path::new("a.tcx")),
+            "application/vnd.garmin.tcx+xml"
+        );
+        assert_eq!(get_content_type(Path::new("a")), "application/octet-stream");
+    }
+}
@@ -1,21 +1,24 @@
 use std::fs;
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 use std::path::Path;
 
+use sha2::{Digest, Sha256};
+
+pub fn is_supported_track_file(filename: &str) -> bool {
+    filename.ends_with(".gpx") || filename.ends_with(".fit") || filename.ends_with(".tcx")
+}
+
 pub fn get_valid_gps_files(path: &Path) -> Result<Vec<String>, Error> {
     let mut file_list = Vec::new();
 
     if !path.is_dir() {
-        return Err(Error::new(
-            ErrorKind::Other,
-            "Provided path is not a directory.",
-        ));
+        return Err(Error::other("Provided path is not a directory."));
     }
 
     for entry in fs::read_dir(path)? {
         let entry = entry?;
         if let Some(filename) = entry.file_name().to_str() {
-            if filename.ends_with(".gpx") || filename.ends_with(".fit") {
+            if is_supported_track_file(filename) {
                 file_list.push(filename.to_string());
             }
         }
@@ -25,10 +28,21 @@ pub fn get_valid_gps_files(path: &Path) -> Result<Vec<String>, Error> {
 }
 
 pub fn read_file(path: &Path) -> Result<String, Error> {
-    println!("Reading file: {}", path.display());
     fs::read_to_string(path)
 }
 
+pub fn read_binary_file(path: &Path) -> Result<Vec<u8>, Error> {
+    fs::read(path)
+}
+
+/// SHA-256 of the content as a hex string
+pub fn get_content_hash(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
 pub fn create_folder(path: &Path) -> Result<(), Error> {
     fs::create_dir_all(path)
 }
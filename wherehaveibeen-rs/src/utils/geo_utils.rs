@@ -0,0 +1,33 @@
+const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+
+/// Great-circle distance in meters between two points given in degrees.
+pub fn haversine_distance(
+    latitude_a: f64,
+    longitude_a: f64,
+    latitude_b: f64,
+    longitude_b: f64,
+) -> f64 {
+    let delta_latitude = (latitude_b - latitude_a).to_radians();
+    let delta_longitude = (longitude_b - longitude_a).to_radians();
+
+    let a = (delta_latitude / 2.0).sin().powi(2)
+        + latitude_a.to_radians().cos()
+            * latitude_b.to_radians().cos()
+            * (delta_longitude / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_haversine_distance() {
+        // Munich - Berlin is around 504 km
+        let distance = haversine_distance(48.1372, 11.5755, 52.5200, 13.4050);
+        assert!((distance - 504_000.0).abs() < 1_000.0);
+
+        assert_eq!(haversine_distance(48.0, 11.0, 48.0, 11.0), 0.0);
+    }
+}
@@ -0,0 +1,92 @@
+use axum::{
+    http::{
+        header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH, VARY},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+
+// The content of a track never changes but it is checked again once a day just in case
+pub const CACHE_FOR_ONE_DAY: &str = "public, max-age=86400";
+// The content can change at any time so the client must always ask if it is still valid
+pub const ALWAYS_REVALIDATE: &str = "no-cache";
+
+/// Weak ETag and `Cache-Control` policy of a response. The tag is weak because the compression
+/// layer may encode the body differently for each client.
+pub struct CacheHeaders {
+    etag: String,
+    cache_control: &'static str,
+}
+
+impl CacheHeaders {
+    pub fn new(tag_parts: &[&str], cache_control: &'static str) -> Self {
+        CacheHeaders {
+            etag: format!("W/\"{}\"", tag_parts.join("-")),
+            cache_control,
+        }
+    }
+
+    /// Whether the client already has this version of the response according to `If-None-Match`
+    pub fn is_fresh(&self, headers: &HeaderMap) -> bool {
+        headers
+            .get_all(IF_NONE_MATCH)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|tag| tag.trim())
+            // If-None-Match uses the weak comparison
+            .any(|tag| {
+                tag == "*" || tag.trim_start_matches("W/") == self.etag.trim_start_matches("W/")
+            })
+    }
+
+    pub fn not_modified(&self) -> Response {
+        self.apply(StatusCode::NOT_MODIFIED)
+    }
+
+    pub fn apply(&self, response: impl IntoResponse) -> Response {
+        (
+            [
+                (ETAG, self.etag.as_str()),
+                (CACHE_CONTROL, self.cache_control),
+                // The format can be chosen with the Accept header
+                (VARY, "Accept"),
+            ],
+            response,
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn if_none_match(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn test_is_fresh() {
+        let cache = CacheHeaders::new(&["heatmap", "3", "json"], ALWAYS_REVALIDATE);
+
+        assert!(cache.is_fresh(&if_none_match("\"heatmap-3-json\"")));
+        assert!(cache.is_fresh(&if_none_match("\"other\", W/\"heatmap-3-json\"")));
+        assert!(cache.is_fresh(&if_none_match("*")));
+        assert!(!cache.is_fresh(&if_none_match("\"heatmap-2-json\"")));
+        assert!(!cache.is_fresh(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn test_not_modified() {
+        let cache = CacheHeaders::new(&["abc"], CACHE_FOR_ONE_DAY);
+        let response = cache.not_modified();
+
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[ETAG], "W/\"abc\"");
+        assert_eq!(response.headers()[CACHE_CONTROL], CACHE_FOR_ONE_DAY);
+    }
+}
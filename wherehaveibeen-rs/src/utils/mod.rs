@@ -1,6 +1,11 @@
 pub mod file_utils;
 pub mod cache_utils;
-pub mod environment;
+pub mod config;
 pub mod activity_type;
+pub mod api_error;
 pub mod api_response;
-pub mod api_utils;
\ No newline at end of file
+pub mod api_utils;
+pub mod geo_utils;
+pub mod file_access;
+pub mod encoding;
+pub mod http_cache;
\ No newline at end of file